@@ -0,0 +1,89 @@
+// Throughput and allocations of the one-shot and streaming APIs.
+// Run with `cargo bench --bench hash`
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use sha::five_twelve;
+use sha::two_five_six;
+
+// Counts every allocation made while hashing
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+const ITERATIONS: usize = 5;
+
+fn bench(name: &str, msg: &[u8], f: impl Fn(&[u8])) {
+    // Warm up
+    f(msg);
+
+    ALLOCATIONS.store(0, Ordering::Relaxed);
+    ALLOCATED_BYTES.store(0, Ordering::Relaxed);
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f(black_box(msg));
+    }
+    let elapsed = start.elapsed();
+
+    let mib = (msg.len() * ITERATIONS) as f64 / (1024.0 * 1024.0);
+    println!(
+        "{name:<28} {:>8.1} MiB/s {:>6} allocs/hash {:>12} bytes allocated/hash",
+        mib / elapsed.as_secs_f64(),
+        ALLOCATIONS.load(Ordering::Relaxed) / ITERATIONS,
+        ALLOCATED_BYTES.load(Ordering::Relaxed) / ITERATIONS,
+    );
+}
+
+fn main() {
+    for size in [1024, 1024 * 1024, 16 * 1024 * 1024] {
+        let msg: Vec<u8> = (0..size).map(|i| i as u8).collect();
+        println!("-- {size} bytes");
+
+        bench("two_five_six::hash", &msg, |m| { black_box(two_five_six::hash(m)); });
+        bench("two_five_six::Context", &msg, |m| {
+            let mut ctx = two_five_six::Context::new();
+            ctx.update(m);
+            black_box(ctx.finalize());
+        });
+        bench("five_twelve::hash", &msg, |m| { black_box(five_twelve::hash(m)); });
+        bench("five_twelve::Context", &msg, |m| {
+            let mut ctx = five_twelve::Context::new();
+            ctx.update(m);
+            black_box(ctx.finalize());
+        });
+    }
+
+    // Many independent 4 KiB messages, one after the other or in lanes
+    let msg: Vec<u8> = (0..1024 * 1024).map(|i| i as u8).collect();
+    println!("-- 4096 byte chunks");
+    bench("two_five_six::hash per chunk", &msg, |m| {
+        for chunk in m.chunks(4096) {
+            black_box(two_five_six::hash(chunk));
+        }
+    });
+    bench("two_five_six::hash_many_into", &msg, |m| {
+        let chunks: [&[u8]; 256] = std::array::from_fn(|i| &m[i * 4096..(i + 1) * 4096]);
+        let mut hashes = [[0u8; 32]; 256];
+        two_five_six::hash_many_into(&chunks, &mut hashes);
+        black_box(hashes);
+    });
+}
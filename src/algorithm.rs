@@ -0,0 +1,62 @@
+//! Common interface of the streaming contexts, so code like HMAC can be written once for every SHA-2 variant.
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::constant_time;
+use crate::hasher::split_bits;
+
+pub trait Algorithm: Default + Clone {
+    // Size of a message block in bytes (64 for the 32bits variants, 128 for the 64bits ones)
+    const BLOCK_BYTES: usize;
+    // Size of the final hash in bytes
+    const OUTPUT_BYTES: usize;
+
+    type Output: AsRef<[u8]> + Copy;
+
+    fn update(&mut self, data: &[u8]);
+
+    // Feeds everything the reader yields until EOF, returns the number of bytes read
+    fn update_reader<R: Read>(&mut self, reader: R) -> io::Result<u64>;
+
+    fn finalize(self) -> Self::Output;
+
+    // Ends a message that doesn't stop on a byte boundary, the last num_bits bits are the upper bits of last_bits
+    fn finalize_bits(self, last_bits: u8, num_bits: u32) -> Self::Output;
+
+    // One-shot hashing through the streaming context
+    fn digest(message: &[u8]) -> Self::Output {
+        let mut ctx = Self::default();
+        ctx.update(message);
+        ctx.finalize()
+    }
+
+    /// One-shot hashing of the first num_bits bits of message (MSB first)
+    ///
+    /// # Panics
+    ///
+    /// If num_bits is more than the bits of message
+    fn digest_bits(message: &[u8], num_bits: u64) -> Self::Output {
+        let (message, trailing_bits) = split_bits(message, num_bits);
+        let Some((&last_bits, whole)) = message.split_last().filter(|_| trailing_bits > 0) else {
+            return Self::digest(message);
+        };
+
+        let mut ctx = Self::default();
+        ctx.update(whole);
+        ctx.finalize_bits(last_bits, trailing_bits)
+    }
+
+    // Checks the hash of message in constant time
+    fn verify(message: &[u8], expected: &[u8]) -> bool {
+        constant_time::eq(Self::digest(message).as_ref(), expected)
+    }
+}
+
+// Hashes a file, for code generic over the algorithm
+pub(crate) fn hash_file<A: Algorithm>(path: &Path) -> io::Result<A::Output> {
+    let mut ctx = A::default();
+    ctx.update_reader(File::open(path)?)?;
+    Ok(ctx.finalize())
+}
@@ -0,0 +1,230 @@
+//! Async versions of the [`crate::stream`] adapters for tokio streams (`tokio` feature).
+//! ```
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() {
+//! use tokio::io::AsyncReadExt;
+//! use sha::async_io::AsyncHashingReader;
+//! use sha::two_five_six::{hash, Context as Sha256};
+//!
+//! let mut reader: AsyncHashingReader<_, Sha256> = AsyncHashingReader::new(&b"Hello, World!"[..]);
+//! let mut content = Vec::new();
+//! reader.read_to_end(&mut content).await.unwrap();
+//! assert_eq!(reader.digest(), hash(b"Hello, World!"));
+//! # }
+//! ```
+
+use std::io;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use pin_project_lite::pin_project;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, ReadBuf};
+
+use crate::algorithm::Algorithm;
+use crate::context::READ_BUFFER_BYTES;
+use crate::stream::{matches, mismatch_error};
+
+/// Hashes everything the reader yields until EOF, through a fixed size buffer
+pub async fn hash_async_reader<A: Algorithm, R: AsyncRead + Unpin>(mut reader: R) -> io::Result<A::Output> {
+    let mut ctx = A::default();
+    let mut buffer = vec![0u8; READ_BUFFER_BYTES];
+
+    loop {
+        let n = reader.read(&mut buffer).await?;
+        if n == 0 {
+            return Ok(ctx.finalize());
+        }
+        ctx.update(&buffer[..n]);
+    }
+}
+
+pin_project! {
+    /// Forwards reads to the inner reader and hashes every byte returned
+    pub struct AsyncHashingReader<R, A: Algorithm> {
+        #[pin]
+        inner: R,
+        ctx: A,
+        expected: Option<A::Output>,
+    }
+}
+
+impl<R: AsyncRead, A: Algorithm> AsyncHashingReader<R, A> {
+    pub fn new(inner: R) -> Self {
+        Self { inner, ctx: A::default(), expected: None }
+    }
+
+    /// Makes the read hitting EOF return an InvalidData error if the digest of everything read isn't `expected`
+    pub fn expect(mut self, expected: A::Output) -> Self {
+        self.expected = Some(expected);
+        self
+    }
+
+    /// Digest of the bytes read so far
+    pub fn digest(&self) -> A::Output {
+        self.ctx.clone().finalize()
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: AsyncRead, A: Algorithm> AsyncRead for AsyncHashingReader<R, A> {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let this = self.project();
+
+        // Only the bytes filled by this call are new
+        let before = buf.filled().len();
+        ready!(this.inner.poll_read(cx, buf))?;
+        let read = &buf.filled()[before..];
+        this.ctx.update(read);
+
+        // An empty buffer also reads 0 bytes, that's not the end of the stream
+        if read.is_empty() && buf.remaining() > 0 {
+            if let Some(expected) = this.expected {
+                if !matches(this.ctx, expected) {
+                    return Poll::Ready(Err(mismatch_error()));
+                }
+            }
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+pin_project! {
+    /// Forwards writes to the inner writer and hashes every byte it accepted
+    pub struct AsyncHashingWriter<W, A: Algorithm> {
+        #[pin]
+        inner: W,
+        ctx: A,
+        expected: Option<A::Output>,
+    }
+}
+
+impl<W: AsyncWrite, A: Algorithm> AsyncHashingWriter<W, A> {
+    pub fn new(inner: W) -> Self {
+        Self { inner, ctx: A::default(), expected: None }
+    }
+
+    /// Makes the shutdown return an InvalidData error if the digest of everything written isn't `expected`
+    pub fn expect(mut self, expected: A::Output) -> Self {
+        self.expected = Some(expected);
+        self
+    }
+
+    /// Digest of the bytes written so far
+    pub fn digest(&self) -> A::Output {
+        self.ctx.clone().finalize()
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: AsyncWrite, A: Algorithm> AsyncWrite for AsyncHashingWriter<W, A> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let this = self.project();
+
+        // Only what the inner writer took, the caller retries the rest
+        let n = ready!(this.inner.poll_write(cx, buf))?;
+        this.ctx.update(&buf[..n]);
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().inner.poll_flush(cx)
+    }
+
+    // The end of the stream for a writer, where the expected digest is checked
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.project();
+        ready!(this.inner.poll_shutdown(cx))?;
+
+        if let Some(expected) = this.expected {
+            if !matches(this.ctx, expected) {
+                return Poll::Ready(Err(mismatch_error()));
+            }
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{duplex, AsyncWriteExt};
+    use crate::two_five_six::{self, Context as Sha256};
+    use crate::five_twelve::{self, Context as Sha512};
+
+    fn message() -> Vec<u8> {
+        (0..50_000u32).map(|i| (i * 13 % 256) as u8).collect()
+    }
+
+    #[tokio::test]
+    async fn test_hash_async_reader() {
+        let msg = message();
+
+        // Smaller than the message so the writer has to wait for the reader
+        let (mut client, server) = duplex(1000);
+        let writer = tokio::spawn({
+            let msg = msg.clone();
+            async move {
+                client.write_all(&msg).await.unwrap();
+            }
+        });
+
+        assert_eq!(two_five_six::hash_async_reader(server).await.unwrap(), two_five_six::hash(&msg));
+        writer.await.unwrap();
+
+        assert_eq!(five_twelve::hash_async_reader(&msg[..]).await.unwrap(), five_twelve::hash(&msg));
+        assert_eq!(five_twelve::hash_async_reader(tokio::io::empty()).await.unwrap(), five_twelve::hash(b""));
+    }
+
+    #[tokio::test]
+    async fn test_reader_writer() {
+        let msg = message();
+        let (client, server) = duplex(777);
+
+        let writer = tokio::spawn({
+            let msg = msg.clone();
+            async move {
+                let mut writer: AsyncHashingWriter<_, Sha256> = AsyncHashingWriter::new(client).expect(two_five_six::hash(&msg));
+                writer.write_all(&msg).await.unwrap();
+                writer.shutdown().await.unwrap();
+                writer.digest()
+            }
+        });
+
+        let mut reader: AsyncHashingReader<_, Sha256> = AsyncHashingReader::new(server).expect(two_five_six::hash(&msg));
+        let mut out = Vec::new();
+        reader.read_to_end(&mut out).await.unwrap();
+
+        assert_eq!(out, msg);
+        assert_eq!(reader.digest(), writer.await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_mismatch() {
+        let msg = message();
+
+        let mut reader: AsyncHashingReader<_, Sha512> = AsyncHashingReader::new(&msg[..]).expect(five_twelve::hash(b""));
+        let mut out = Vec::new();
+        assert_eq!(reader.read_to_end(&mut out).await.unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert_eq!(out, msg);
+
+        let mut writer: AsyncHashingWriter<_, Sha512> = AsyncHashingWriter::new(Vec::new()).expect(five_twelve::hash(b""));
+        writer.write_all(&msg).await.unwrap();
+        assert_eq!(writer.shutdown().await.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,133 @@
+//! Hashing many files or buffers at once on a pool of threads. Results come back in input order,
+//! a file that can't be read only fails its own item.
+//! ```
+//! use sha::batch;
+//! use sha::two_five_six::{hash, Context as Sha256};
+//!
+//! let hashes = batch::hash_buffers::<Sha256>(&[b"abc", b"Hello, World!"], 0);
+//! assert_eq!(hashes, vec![hash(b"abc"), hash(b"Hello, World!")]);
+//!
+//! let results = batch::hash_files::<Sha256, _>(&["Cargo.toml", "missing"], 4);
+//! assert!(results[0].is_ok() && results[1].is_err());
+//! ```
+
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use crate::algorithm::{self, Algorithm};
+
+/// Calls f on every item from up to `threads` threads (0 for one per CPU) and returns the results in input order.
+/// Items are handed out one at a time, so a few large items don't leave the other threads idle.
+pub fn parallel_map<T, R, F>(items: &[T], threads: usize, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let threads = match threads {
+        0 => thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    };
+    let threads = usize::min(threads, items.len());
+    if threads <= 1 {
+        return items.iter().map(f).collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let mut indexed: Vec<(usize, R)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| scope.spawn(|| {
+                let mut done = Vec::new();
+                loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    if i >= items.len() {
+                        return done;
+                    }
+                    done.push((i, f(&items[i])));
+                }
+            }))
+            .collect();
+
+        // A panic in f is passed on to the caller
+        workers.into_iter().flat_map(|worker| worker.join().unwrap_or_else(|e| std::panic::resume_unwind(e))).collect()
+    });
+
+    indexed.sort_unstable_by_key(|(i, _)| *i);
+    indexed.into_iter().map(|(_, result)| result).collect()
+}
+
+pub fn hash_buffers<A>(buffers: &[&[u8]], threads: usize) -> Vec<A::Output>
+where
+    A: Algorithm,
+    A::Output: Send,
+{
+    parallel_map(buffers, threads, |buffer| A::digest(buffer))
+}
+
+pub fn hash_files<A, P>(paths: &[P], threads: usize) -> Vec<io::Result<A::Output>>
+where
+    A: Algorithm,
+    A::Output: Send,
+    P: AsRef<Path> + Sync,
+{
+    parallel_map(paths, threads, |path| algorithm::hash_file::<A>(path.as_ref()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use crate::two_five_six::{self, Context as Sha256};
+    use crate::five_twelve::{self, Context as Sha512};
+
+    #[test]
+    fn test_parallel_map() {
+        let items: Vec<u64> = (0..1000).collect();
+        let expected: Vec<u64> = items.iter().map(|i| i * i).collect();
+
+        for threads in [0, 1, 2, 7, 2000] {
+            assert_eq!(parallel_map(&items, threads, |i| i * i), expected);
+        }
+        assert_eq!(parallel_map(&[] as &[u64], 4, |i| i * i), Vec::<u64>::new());
+    }
+
+    #[test]
+    fn test_hash_buffers() {
+        let messages: Vec<Vec<u8>> = (0..200u32).map(|i| vec![i as u8; i as usize * 37]).collect();
+        let buffers: Vec<&[u8]> = messages.iter().map(Vec::as_slice).collect();
+
+        let hashes = hash_buffers::<Sha256>(&buffers, 4);
+        for (message, hash) in messages.iter().zip(&hashes) {
+            assert_eq!(*hash, two_five_six::hash(message));
+        }
+        assert_eq!(hashes.len(), messages.len());
+    }
+
+    #[test]
+    fn test_hash_files() {
+        let dir = std::env::temp_dir().join(format!("sha_batch_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut paths = Vec::new();
+        for i in 0..50u32 {
+            let path = dir.join(format!("{i}.bin"));
+            fs::write(&path, vec![i as u8; i as usize * 1000]).unwrap();
+            paths.push(path);
+        }
+        // Errors stay in their slot
+        paths.insert(10, dir.join("missing"));
+
+        let results = hash_files::<Sha512, _>(&paths, 8);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(results.len(), 51);
+        assert_eq!(results[10].as_ref().unwrap_err().kind(), io::ErrorKind::NotFound);
+        for (i, result) in results.iter().enumerate().filter(|(i, _)| *i != 10) {
+            let n = if i < 10 { i } else { i - 1 };
+            assert_eq!(*result.as_ref().unwrap(), five_twelve::hash(&vec![n as u8; n * 1000]));
+        }
+    }
+}
@@ -0,0 +1,175 @@
+// --check: verifies the files listed in checksum files, with the same messages as coreutils
+
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+use sha::checksum::{self, escape_bytes, Entry};
+use sha::constant_time;
+use sha::tree;
+
+use crate::{error_message, hash_file, path_from_bytes, print_bytes, Algorithm, Options};
+
+// Tally of a checksum file, for the warnings printed at the end
+#[derive(Default)]
+struct Counts {
+    entries: usize,
+    improperly_formatted: usize,
+    unreadable: usize,
+    mismatched: usize,
+    matched: usize,
+}
+
+// As bytes, file names in checksum lines don't have to be UTF-8
+fn read_checksum_file(file: &Path) -> io::Result<Vec<u8>> {
+    if file.as_os_str() == "-" {
+        let mut content = Vec::new();
+        io::stdin().lock().read_to_end(&mut content)?;
+        return Ok(content);
+    }
+    fs::read(file)
+}
+
+// The algorithm named by a BSD line, else the one given with -a, else the one with that digest length
+fn entry_algorithm<N>(options: &Options, entry: &Entry<N>) -> Option<Algorithm> {
+    let algorithm = match &entry.algorithm {
+        Some(tag) => Algorithm::from_tag(tag)?,
+        None => options.algorithm.or_else(|| Algorithm::from_output_bytes(entry.digest.len()))?,
+    };
+
+    if algorithm.output_bytes() != entry.digest.len() {
+        return None;
+    }
+    Some(algorithm)
+}
+
+// Summary line printed by --tree, like SHA256-TREE (dir) = hex
+fn is_tree_digest<N>(entry: &Entry<N>) -> bool {
+    entry.algorithm.as_deref().and_then(|tag| tag.strip_suffix("-TREE")).and_then(Algorithm::from_tag).is_some()
+}
+
+// Prints the result of checking one file, after its escaped name
+fn print_result(name: &[u8], result: &str) {
+    let (prefix, name) = escape_bytes(name);
+    print_bytes(&[prefix.as_bytes(), &name, format!(": {result}\n").as_bytes()].concat());
+}
+
+fn plural(n: usize, singular: &str, plural: &str) -> String {
+    if n == 1 {
+        format!("1 {singular}")
+    } else {
+        format!("{n} {plural}")
+    }
+}
+
+// Checks every line of one checksum file, returns false if anything failed
+pub fn check_file(options: &Options, file: &Path) -> bool {
+    let content = match read_checksum_file(file) {
+        Ok(content) => content,
+        Err(e) => {
+            eprintln!("sha2sum: {}: {}", file.display(), error_message(&e));
+            return false;
+        }
+    };
+
+    let mut counts = Counts::default();
+    // Each line on its own, one that isn't UTF-8 only matters for its file name
+    for line in content.split(|&c| c == b'\n') {
+        if let Some((path, target)) = std::str::from_utf8(line).ok().and_then(tree::parse_symlink_line) {
+            counts.entries += 1;
+            check_symlink(options, &mut counts, &path, &target);
+            continue;
+        }
+
+        let entry = match checksum::parse_line_bytes(line) {
+            Ok(None) => continue,
+            // The directory it names is relative to where --tree ran, not to the manifest, so it can't be checked here
+            Ok(Some(entry)) if is_tree_digest(&entry) => continue,
+            entry => entry.ok().flatten(),
+        };
+        let Some((entry, algorithm)) = entry.and_then(|entry| entry_algorithm(options, &entry).map(|a| (entry, a))) else {
+            counts.improperly_formatted += 1;
+            continue;
+        };
+        counts.entries += 1;
+
+        let path = path_from_bytes(&entry.file_name);
+        match hash_file(algorithm, &path) {
+            Err(e) if options.ignore_missing && e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => {
+                counts.unreadable += 1;
+                if !options.status {
+                    eprintln!("sha2sum: {}: {}", path.display(), error_message(&e));
+                    print_result(&entry.file_name, "FAILED open or read");
+                }
+            }
+            Ok(hash) if constant_time::eq(&hash, &entry.digest) => {
+                counts.matched += 1;
+                if !options.status && !options.quiet {
+                    print_result(&entry.file_name, "OK");
+                }
+            }
+            Ok(_) => {
+                counts.mismatched += 1;
+                if !options.status {
+                    print_result(&entry.file_name, "FAILED");
+                }
+            }
+        }
+    }
+
+    if counts.entries == 0 {
+        if !options.status {
+            eprintln!("sha2sum: {}: no properly formatted checksum lines found", file.display());
+        }
+        return false;
+    }
+
+    if !options.status {
+        if counts.improperly_formatted > 0 {
+            eprintln!("sha2sum: WARNING: {} improperly formatted", plural(counts.improperly_formatted, "line is", "lines are"));
+        }
+        if counts.unreadable > 0 {
+            eprintln!("sha2sum: WARNING: {} not be read", plural(counts.unreadable, "listed file could", "listed files could"));
+        }
+        if counts.mismatched > 0 {
+            eprintln!("sha2sum: WARNING: {} NOT match", plural(counts.mismatched, "computed checksum did", "computed checksums did"));
+        }
+    }
+
+    let nothing_verified = options.ignore_missing && counts.matched == 0;
+    if nothing_verified && !options.status {
+        eprintln!("sha2sum: {}: no file was verified", file.display());
+    }
+
+    counts.unreadable == 0
+        && counts.mismatched == 0
+        && !(options.strict && counts.improperly_formatted > 0)
+        && !nothing_verified
+}
+
+// A SYMLINK line of a --tree manifest: the link must still point to the same target
+fn check_symlink(options: &Options, counts: &mut Counts, path: &str, target: &str) {
+    match tree::read_link_target(Path::new(path)) {
+        Err(e) if options.ignore_missing && e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => {
+            counts.unreadable += 1;
+            if !options.status {
+                eprintln!("sha2sum: {path}: {}", error_message(&e));
+                print_result(path.as_bytes(), "FAILED open or read");
+            }
+        }
+        Ok(actual) if actual == target => {
+            counts.matched += 1;
+            if !options.status && !options.quiet {
+                print_result(path.as_bytes(), "OK");
+            }
+        }
+        Ok(_) => {
+            counts.mismatched += 1;
+            if !options.status {
+                print_result(path.as_bytes(), "FAILED");
+            }
+        }
+    }
+}
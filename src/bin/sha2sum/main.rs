@@ -0,0 +1,362 @@
+// sha2sum: prints or checks SHA-2 checksums, compatible with the GNU coreutils sha*sum output.
+//
+// sha2sum [-a 224|256|384|512] [--tag] [-j N] [FILE]...
+// sha2sum --check [--quiet|--status] [--strict] [--ignore-missing] [FILE]...
+// sha2sum --tree [-a 224|256|384|512] [--follow-symlinks] [--digest-only] DIR...
+//   (an option, a 'tree' subcommand would stop 'sha2sum tree' from hashing a file named tree)
+// With no FILE, or when FILE is -, reads standard input.
+
+mod check;
+mod tree;
+
+use std::borrow::Cow;
+use std::env;
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use sha::{Context, Sha2Params};
+use sha::batch::parallel_map;
+use sha::checksum::escape_bytes;
+use sha::output::to_hex;
+use sha::two_two_four::Sha224;
+use sha::two_five_six::Sha256;
+use sha::three_eight_four::Sha384;
+use sha::five_twelve::Sha512;
+
+const USAGE: &str = "Usage: sha2sum [OPTION]... [FILE]...
+Print or check SHA-2 checksums. With no FILE, or when FILE is -, read standard input.
+
+  -a, --algorithm BITS  224, 256 (default), 384 or 512
+  -b, --binary          read in binary mode (marks the lines with '*')
+  -c, --check           read checksums from the FILEs and check them
+  -j, --jobs N          hash N files at a time, 0 for one per CPU (default 1)
+  -t, --text            read in text mode (default)
+      --tag             create a BSD-style checksum
+      --tree            print a manifest and a digest of each directory FILE
+  -h, --help            display this help and exit
+
+The following options are useful only when verifying checksums:
+      --ignore-missing  don't fail or report status for missing files
+      --quiet           don't print OK for each successfully verified file
+      --status          don't output anything, status code shows success
+      --strict          exit non-zero for improperly formatted checksum lines
+
+The following options are useful only with --tree:
+  -L, --follow-symlinks hash what symbolic links point to instead of recording their target
+      --digest-only     only print the tree digest
+
+When checking, the algorithm is given by BSD lines, or by -a, or else guessed from the digest length.
+
+With --tree, every regular file under DIR is listed in the checksum format and symbolic links as
+'SYMLINK (path) -> target', followed by the digest of the whole tree. The listing can be checked with
+'sha2sum --check' from inside DIR, the tree digest line is skipped there.
+It's an option and not a 'sha2sum tree' subcommand because, like with sha256sum, every operand is a file:
+'sha2sum tree' hashes a file named tree.
+";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Algorithm {
+    Sha224,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl Algorithm {
+    fn from_tag(tag: &str) -> Option<Algorithm> {
+        [Algorithm::Sha224, Algorithm::Sha256, Algorithm::Sha384, Algorithm::Sha512].into_iter().find(|a| a.tag() == tag)
+    }
+
+    fn from_output_bytes(num_bytes: usize) -> Option<Algorithm> {
+        [Algorithm::Sha224, Algorithm::Sha256, Algorithm::Sha384, Algorithm::Sha512].into_iter().find(|a| a.output_bytes() == num_bytes)
+    }
+
+    fn output_bytes(self) -> usize {
+        match self {
+            Algorithm::Sha224 => Sha224::OUTPUT_BYTES,
+            Algorithm::Sha256 => Sha256::OUTPUT_BYTES,
+            Algorithm::Sha384 => Sha384::OUTPUT_BYTES,
+            Algorithm::Sha512 => Sha512::OUTPUT_BYTES,
+        }
+    }
+
+    fn from_bits(bits: &str) -> Option<Algorithm> {
+        match bits {
+            "224" => Some(Algorithm::Sha224),
+            "256" => Some(Algorithm::Sha256),
+            "384" => Some(Algorithm::Sha384),
+            "512" => Some(Algorithm::Sha512),
+            _ => None,
+        }
+    }
+
+    // Name used by the BSD format
+    fn tag(self) -> &'static str {
+        match self {
+            Algorithm::Sha224 => "SHA224",
+            Algorithm::Sha256 => "SHA256",
+            Algorithm::Sha384 => "SHA384",
+            Algorithm::Sha512 => "SHA512",
+        }
+    }
+
+    fn hash_reader<R: Read>(self, reader: R) -> io::Result<Vec<u8>> {
+        match self {
+            Algorithm::Sha224 => hash_reader::<Sha224, R>(reader),
+            Algorithm::Sha256 => hash_reader::<Sha256, R>(reader),
+            Algorithm::Sha384 => hash_reader::<Sha384, R>(reader),
+            Algorithm::Sha512 => hash_reader::<Sha512, R>(reader),
+        }
+    }
+}
+
+fn hash_reader<P: Sha2Params, R: Read>(reader: R) -> io::Result<Vec<u8>> {
+    let mut ctx: Context<P> = Context::new();
+    ctx.update_reader(reader)?;
+    Ok(ctx.finalize().as_ref().to_vec())
+}
+
+struct Options {
+    // None when not given on the command line, hashing then uses SHA-256
+    algorithm: Option<Algorithm>,
+    binary: bool,
+    tag: bool,
+    check: bool,
+    ignore_missing: bool,
+    quiet: bool,
+    status: bool,
+    strict: bool,
+    // Files hashed at the same time, 0 for one per CPU
+    jobs: usize,
+    tree: bool,
+    follow_symlinks: bool,
+    digest_only: bool,
+    help: bool,
+    // Any name the OS accepts, it only becomes text when printed
+    files: Vec<PathBuf>,
+}
+
+// The argument following the option arg
+fn option_value<I: Iterator<Item = OsString>>(args: &mut I, arg: &str) -> Result<String, String> {
+    let value = args.next().ok_or(format!("option '{arg}' requires an argument"))?;
+    Ok(value.to_string_lossy().into_owned())
+}
+
+fn parse_args<I: Iterator<Item = OsString>>(mut args: I) -> Result<Options, String> {
+    let mut options = Options {
+        algorithm: None,
+        binary: false,
+        tag: false,
+        check: false,
+        ignore_missing: false,
+        quiet: false,
+        status: false,
+        strict: false,
+        jobs: 1,
+        tree: false,
+        follow_symlinks: false,
+        digest_only: false,
+        help: false,
+        files: Vec::new(),
+    };
+
+    while let Some(arg) = args.next() {
+        // Options are ASCII, an argument that isn't valid UTF-8 is a file or an unrecognized option
+        let name = arg.to_string_lossy().into_owned();
+        match &*name {
+            "-a" | "--algorithm" => {
+                let bits = option_value(&mut args, &name)?;
+                options.algorithm = Some(Algorithm::from_bits(&bits).ok_or(format!("invalid algorithm '{bits}'"))?);
+            }
+            "-b" | "--binary" => options.binary = true,
+            "-j" | "--jobs" => {
+                let jobs = option_value(&mut args, &name)?;
+                options.jobs = jobs.parse().map_err(|_| format!("invalid number of jobs '{jobs}'"))?;
+            }
+            "-c" | "--check" => options.check = true,
+            "--ignore-missing" => options.ignore_missing = true,
+            "--quiet" => options.quiet = true,
+            "--status" => options.status = true,
+            "--strict" => options.strict = true,
+            "-t" | "--text" => options.binary = false,
+            "--tag" => options.tag = true,
+            "--tree" => options.tree = true,
+            "-L" | "--follow-symlinks" => options.follow_symlinks = true,
+            "--digest-only" => options.digest_only = true,
+            "-h" | "--help" => options.help = true,
+            // Everything after it is a file, even -h
+            "--" => {
+                options.files.extend(args.by_ref().map(PathBuf::from));
+            }
+            _ if name.starts_with("--algorithm=") => {
+                let bits = &name["--algorithm=".len()..];
+                options.algorithm = Some(Algorithm::from_bits(bits).ok_or(format!("invalid algorithm '{bits}'"))?);
+            }
+            // A lone - is standard input
+            _ if name.starts_with('-') && name != "-" => return Err(format!("unrecognized option '{name}'")),
+            _ => options.files.push(PathBuf::from(arg)),
+        }
+    }
+
+    // The other options don't matter, help is printed whatever they are
+    if options.help {
+        return Ok(options);
+    }
+
+    if options.tree {
+        if options.check || options.tag {
+            return Err(String::from("the --tree option can't be combined with --check or --tag"));
+        }
+        if options.files.is_empty() {
+            return Err(String::from("missing directory operand"));
+        }
+    } else {
+        for (set, name) in [(options.follow_symlinks, "--follow-symlinks"), (options.digest_only, "--digest-only")] {
+            if set {
+                return Err(format!("the {name} option is meaningful only with --tree"));
+            }
+        }
+    }
+
+    if options.files.is_empty() {
+        options.files.push(PathBuf::from("-"));
+    }
+
+    if options.check && options.tag {
+        return Err(String::from("the --tag option is meaningless when verifying checksums"));
+    }
+    if !options.check {
+        for (set, name) in [(options.ignore_missing, "--ignore-missing"), (options.quiet, "--quiet"), (options.status, "--status"), (options.strict, "--strict")] {
+            if set {
+                return Err(format!("the {name} option is meaningful only when verifying checksums"));
+            }
+        }
+    }
+
+    Ok(options)
+}
+
+// The name as written in checksum lines: its raw bytes on unix like coreutils, elsewhere names are Unicode
+#[cfg(unix)]
+fn name_bytes(path: &Path) -> Cow<'_, [u8]> {
+    use std::os::unix::ffi::OsStrExt;
+    Cow::Borrowed(path.as_os_str().as_bytes())
+}
+
+#[cfg(not(unix))]
+fn name_bytes(path: &Path) -> Cow<'_, [u8]> {
+    match path.to_string_lossy() {
+        Cow::Borrowed(name) => Cow::Borrowed(name.as_bytes()),
+        Cow::Owned(name) => Cow::Owned(name.into_bytes()),
+    }
+}
+
+// Back from the bytes of a checksum line to a path
+#[cfg(unix)]
+fn path_from_bytes(name: &[u8]) -> PathBuf {
+    use std::os::unix::ffi::OsStrExt;
+    PathBuf::from(std::ffi::OsStr::from_bytes(name))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(name: &[u8]) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(name).into_owned())
+}
+
+// Line ending with a newline, as bytes since file names may not be UTF-8
+fn format_line(options: &Options, file: &Path, hash: &[u8]) -> Vec<u8> {
+    let (prefix, name) = escape_bytes(&name_bytes(file));
+    let (start, end) = if options.tag {
+        (format!("{prefix}{} (", options.algorithm.unwrap_or(Algorithm::Sha256).tag()), format!(") = {}\n", to_hex(hash)))
+    } else {
+        let mode = if options.binary { '*' } else { ' ' };
+        (format!("{prefix}{} {mode}", to_hex(hash)), String::from("\n"))
+    };
+    [start.as_bytes(), &name, end.as_bytes()].concat()
+}
+
+// Writes to standard output, panics like println! when that fails
+fn print_bytes(bytes: &[u8]) {
+    if let Err(e) = io::stdout().lock().write_all(bytes) {
+        panic!("failed printing to stdout: {e}");
+    }
+}
+
+// The OS message without Rust's " (os error N)" suffix, like coreutils prints it
+fn error_message(e: &io::Error) -> String {
+    let message = e.to_string();
+    match message.find(" (os error ") {
+        Some(end) => message[..end].to_string(),
+        None => message,
+    }
+}
+
+fn hash_file(algorithm: Algorithm, file: &Path) -> io::Result<Vec<u8>> {
+    if file.as_os_str() == "-" {
+        return algorithm.hash_reader(io::stdin().lock());
+    }
+    algorithm.hash_reader(File::open(file)?)
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(env::args_os().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("sha2sum: {e}");
+            eprintln!("Try 'sha2sum --help' for more information.");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if options.help {
+        print!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+
+    if options.tree {
+        return if tree::print_trees(&options) { ExitCode::SUCCESS } else { ExitCode::FAILURE };
+    }
+
+    if options.check {
+        let mut failed = false;
+        for file in &options.files {
+            failed |= !check::check_file(&options, file);
+        }
+        return if failed { ExitCode::FAILURE } else { ExitCode::SUCCESS };
+    }
+
+    let algorithm = options.algorithm.unwrap_or(Algorithm::Sha256);
+    let mut stdout = io::stdout().lock();
+    let mut failed = false;
+    let mut report = |file: &Path, result: io::Result<Vec<u8>>| -> io::Result<()> {
+        match result {
+            Ok(hash) => stdout.write_all(&format_line(&options, file, &hash))?,
+            Err(e) => {
+                eprintln!("sha2sum: {}: {}", file.display(), error_message(&e));
+                failed = true;
+            }
+        }
+        Ok(())
+    };
+
+    // One at a time, each line is printed as soon as its file is hashed
+    if options.jobs == 1 {
+        for file in &options.files {
+            if report(file, hash_file(algorithm, file)).is_err() {
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        let results = parallel_map(&options.files, options.jobs, |file| hash_file(algorithm, file));
+        for (file, result) in options.files.iter().zip(results) {
+            if report(file, result).is_err() {
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    if failed { ExitCode::FAILURE } else { ExitCode::SUCCESS }
+}
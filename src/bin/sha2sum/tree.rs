@@ -0,0 +1,51 @@
+// --tree: manifest and tree digest of directories, see sha::tree
+
+use std::path::Path;
+
+use sha::checksum::escape_bytes;
+use sha::output::to_hex;
+use sha::tree::{hash_tree, Symlinks};
+use sha::two_two_four::Context as Sha224;
+use sha::two_five_six::Context as Sha256;
+use sha::three_eight_four::Context as Sha384;
+use sha::five_twelve::Context as Sha512;
+
+use crate::{error_message, name_bytes, print_bytes, Algorithm, Options};
+
+// The manifest and the tree digest of dir, in the output format. As bytes, dir may not be UTF-8
+fn tree_output<A: sha::algorithm::Algorithm>(options: &Options, algorithm: Algorithm, dir: &Path) -> std::io::Result<Vec<u8>> {
+    let symlinks = if options.follow_symlinks { Symlinks::Follow } else { Symlinks::Record };
+    let tree = hash_tree::<A>(dir, symlinks)?;
+    let (prefix, name) = escape_bytes(&name_bytes(dir));
+
+    let mut output = if options.digest_only { Vec::new() } else { tree.manifest().into_bytes() };
+    output.extend_from_slice(format!("{prefix}{}-TREE (", algorithm.tag()).as_bytes());
+    output.extend_from_slice(&name);
+    output.extend_from_slice(format!(") = {}\n", to_hex(tree.digest().as_ref())).as_bytes());
+    Ok(output)
+}
+
+// Prints every directory of options.files, returns false if one couldn't be hashed
+pub fn print_trees(options: &Options) -> bool {
+    let algorithm = options.algorithm.unwrap_or(Algorithm::Sha256);
+
+    let mut ok = true;
+    for dir in &options.files {
+        let output = match algorithm {
+            Algorithm::Sha224 => tree_output::<Sha224>(options, algorithm, dir),
+            Algorithm::Sha256 => tree_output::<Sha256>(options, algorithm, dir),
+            Algorithm::Sha384 => tree_output::<Sha384>(options, algorithm, dir),
+            Algorithm::Sha512 => tree_output::<Sha512>(options, algorithm, dir),
+        };
+
+        match output {
+            Ok(output) => print_bytes(&output),
+            Err(e) => {
+                eprintln!("sha2sum: {}", error_message(&e));
+                ok = false;
+            }
+        }
+    }
+
+    ok
+}
@@ -0,0 +1,237 @@
+//! Parser for checksum files (SHA256SUMS, SHA512SUMS...) as written by the GNU coreutils sha*sum tools,
+//! in the default format and in the BSD one (`--tag`).
+//! ```
+//! use sha::checksum::parse_line;
+//!
+//! let gnu = parse_line("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad  abc.txt").unwrap().unwrap();
+//! let bsd = parse_line("SHA256 (abc.txt) = ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad").unwrap().unwrap();
+//! assert_eq!(gnu.file_name, "abc.txt");
+//! assert_eq!(gnu.digest, bsd.digest);
+//! assert_eq!(bsd.algorithm.as_deref(), Some("SHA256"));
+//! ```
+
+use std::fmt;
+
+use crate::output;
+
+/// Returned for a line that is neither in the GNU nor in the BSD format
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MalformedLine;
+
+impl fmt::Display for MalformedLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "improperly formatted checksum line")
+    }
+}
+
+impl std::error::Error for MalformedLine {}
+
+/// One line of a checksum file. The file name is a `String`, or the raw bytes with [`parse_line_bytes`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry<N = String> {
+    // Algorithm named by a BSD line ("SHA256"), the GNU format doesn't say
+    pub algorithm: Option<String>,
+    pub digest: Vec<u8>,
+    pub file_name: N,
+    // Marked with '*' in the GNU format, it makes no difference to the hash
+    pub binary: bool,
+}
+
+/// Parses a line of a checksum file. Empty lines and comments (starting with '#') give `Ok(None)`.
+pub fn parse_line(line: &str) -> Result<Option<Entry>, MalformedLine> {
+    let Some(entry) = parse_line_bytes(line.as_bytes())? else {
+        return Ok(None);
+    };
+
+    // Only ASCII was split on or unescaped, the name is still valid UTF-8
+    let file_name = String::from_utf8(entry.file_name).map_err(|_| MalformedLine)?;
+    Ok(Some(Entry { algorithm: entry.algorithm, digest: entry.digest, file_name, binary: entry.binary }))
+}
+
+/// Same as [`parse_line`] for a line that isn't necessarily UTF-8, coreutils writes file names as their raw bytes
+pub fn parse_line_bytes(line: &[u8]) -> Result<Option<Entry<Vec<u8>>>, MalformedLine> {
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    if line.trim_ascii().is_empty() || line.starts_with(b"#") {
+        return Ok(None);
+    }
+
+    // Names with a backslash or a newline are escaped, and the line starts with a backslash
+    let (escaped, line) = match line.strip_prefix(b"\\") {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+
+    let mut entry = parse_bsd(line).or_else(|| parse_gnu(line)).ok_or(MalformedLine)?;
+    if escaped {
+        entry.file_name = unescape_bytes(&entry.file_name).ok_or(MalformedLine)?;
+    }
+
+    Ok(Some(entry))
+}
+
+/// Parses a whole checksum file, keeping the line number (starting at 1) of each entry or error
+pub fn parse(content: &str) -> Vec<(usize, Result<Entry, MalformedLine>)> {
+    content
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| parse_line(line).transpose().map(|entry| (i + 1, entry)))
+        .collect()
+}
+
+fn split_once<'a>(s: &'a [u8], separator: &[u8]) -> Option<(&'a [u8], &'a [u8])> {
+    let i = s.windows(separator.len()).position(|w| w == separator)?;
+    Some((&s[..i], &s[i + separator.len()..]))
+}
+
+fn rsplit_once<'a>(s: &'a [u8], separator: &[u8]) -> Option<(&'a [u8], &'a [u8])> {
+    let i = s.windows(separator.len()).rposition(|w| w == separator)?;
+    Some((&s[..i], &s[i + separator.len()..]))
+}
+
+// ALGORITHM (file name) = hex
+fn parse_bsd(line: &[u8]) -> Option<Entry<Vec<u8>>> {
+    let (algorithm, rest) = split_once(line, b" (")?;
+    let (file_name, hex) = rsplit_once(rest, b") = ")?;
+    if algorithm.is_empty() || !algorithm.iter().all(|&c| c.is_ascii_alphanumeric() || c == b'-' || c == b'/') {
+        return None;
+    }
+
+    // Checked to be ASCII just above
+    let algorithm = String::from_utf8(algorithm.to_vec()).ok()?;
+    Some(Entry { algorithm: Some(algorithm), digest: from_hex(hex)?, file_name: file_name.to_vec(), binary: false })
+}
+
+// hex, a space, then ' ' for text mode or '*' for binary mode, then the file name
+fn parse_gnu(line: &[u8]) -> Option<Entry<Vec<u8>>> {
+    let (hex, rest) = split_once(line, b" ")?;
+    let binary = match rest.first()? {
+        b' ' => false,
+        b'*' => true,
+        _ => return None,
+    };
+
+    let file_name = &rest[1..];
+    if file_name.is_empty() {
+        return None;
+    }
+
+    Some(Entry { algorithm: None, digest: from_hex(hex)?, file_name: file_name.to_vec(), binary })
+}
+
+fn from_hex(hex: &[u8]) -> Option<Vec<u8>> {
+    output::from_hex(std::str::from_utf8(hex).ok()?).ok().filter(|digest| !digest.is_empty())
+}
+
+/// Escapes a file name like the sha*sum tools do. Returns the escaped name and what the line must start with:
+/// a backslash when the name has a backslash or a newline, nothing otherwise.
+pub fn escape(name: &str) -> (&'static str, String) {
+    let (prefix, escaped) = escape_bytes(name.as_bytes());
+    // Only ASCII was replaced, by ASCII
+    (prefix, String::from_utf8(escaped).unwrap())
+}
+
+/// Same as [`escape`] for a name given as raw bytes
+pub fn escape_bytes(name: &[u8]) -> (&'static str, Vec<u8>) {
+    if !name.iter().any(|c| matches!(c, b'\\' | b'\n' | b'\r')) {
+        return ("", name.to_vec());
+    }
+
+    let mut escaped = Vec::with_capacity(name.len() + 2);
+    for &c in name {
+        match c {
+            b'\\' => escaped.extend_from_slice(b"\\\\"),
+            b'\n' => escaped.extend_from_slice(b"\\n"),
+            b'\r' => escaped.extend_from_slice(b"\\r"),
+            _ => escaped.push(c),
+        }
+    }
+    ("\\", escaped)
+}
+
+fn unescape_bytes(name: &[u8]) -> Option<Vec<u8>> {
+    let mut unescaped = Vec::with_capacity(name.len());
+    let mut bytes = name.iter();
+
+    while let Some(&c) = bytes.next() {
+        if c != b'\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match bytes.next()? {
+            b'\\' => unescaped.push(b'\\'),
+            b'n' => unescaped.push(b'\n'),
+            b'r' => unescaped.push(b'\r'),
+            _ => return None,
+        }
+    }
+
+    Some(unescaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ABC_256: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+    fn entry(algorithm: Option<&str>, file_name: &str, binary: bool) -> Entry {
+        Entry { algorithm: algorithm.map(String::from), digest: from_hex(ABC_256.as_bytes()).unwrap(), file_name: file_name.to_string(), binary }
+    }
+
+    #[test]
+    fn test_gnu() {
+        assert_eq!(parse_line(&format!("{ABC_256}  abc.txt")), Ok(Some(entry(None, "abc.txt", false))));
+        assert_eq!(parse_line(&format!("{ABC_256} *abc.txt")), Ok(Some(entry(None, "abc.txt", true))));
+        assert_eq!(parse_line(&format!("{}  dir/with space ", ABC_256.to_uppercase())), Ok(Some(entry(None, "dir/with space ", false))));
+        assert_eq!(parse_line(&format!("{ABC_256}  abc.txt\r")), Ok(Some(entry(None, "abc.txt", false))));
+    }
+
+    #[test]
+    fn test_bsd() {
+        assert_eq!(parse_line(&format!("SHA256 (abc.txt) = {ABC_256}")), Ok(Some(entry(Some("SHA256"), "abc.txt", false))));
+        assert_eq!(parse_line(&format!("SHA512/256 (a (1).txt) = {ABC_256}")), Ok(Some(entry(Some("SHA512/256"), "a (1).txt", false))));
+    }
+
+    #[test]
+    fn test_escaped() {
+        assert_eq!(escape("plain name"), ("", String::from("plain name")));
+        assert_eq!(escape("new\nline\\"), ("\\", String::from("new\\nline\\\\")));
+        assert_eq!(parse_line(&format!("\\{ABC_256}  new\\nline\\\\")), Ok(Some(entry(None, "new\nline\\", false))));
+        assert_eq!(parse_line(&format!("\\SHA256 (new\\nline) = {ABC_256}")), Ok(Some(entry(Some("SHA256"), "new\nline", false))));
+        assert_eq!(parse_line(&format!("\\{ABC_256}  bad\\x")), Err(MalformedLine));
+        assert_eq!(escape_bytes(b"\xff\n"), ("\\", b"\xff\\n".to_vec()));
+    }
+
+    #[test]
+    fn test_bytes() {
+        // Latin-1 name, as written by coreutils
+        let line = [format!("{ABC_256}  caf").as_bytes(), b"\xe9"].concat();
+        let entry = parse_line_bytes(&line).unwrap().unwrap();
+        assert_eq!(entry.file_name, b"caf\xe9");
+        assert_eq!(parse_line_bytes(&[b"\\SHA256 (\xe9\\n) = ", ABC_256.as_bytes()].concat()).unwrap().unwrap().file_name, b"\xe9\n");
+        assert_eq!(parse_line_bytes(b"\xe9\xe9  file"), Err(MalformedLine));
+        assert_eq!(parse_line(&format!("{ABC_256}  caf\u{e9}")).unwrap().unwrap().file_name, "caf\u{e9}");
+    }
+
+    #[test]
+    fn test_malformed() {
+        assert_eq!(parse_line(""), Ok(None));
+        assert_eq!(parse_line("# comment"), Ok(None));
+
+        for line in ["abc", "zz  file", &format!("{ABC_256} file"), &format!("{ABC_256}  "), &format!("{}  file", &ABC_256[1..]), "SHA256 (file) = xyz", &format!("(file) = {ABC_256}"), &format!("{}  file", "+f".repeat(32)), &format!("SHA256 (file) = {}", "+f".repeat(32))] {
+            assert_eq!(parse_line(line), Err(MalformedLine), "{line}");
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let content = format!("# SHA256SUMS\n{ABC_256}  a\n\nnot a checksum\nSHA256 (b) = {ABC_256}\n");
+        let entries = parse(&content);
+
+        assert_eq!(entries, vec![
+            (2, Ok(entry(None, "a", false))),
+            (4, Err(MalformedLine)),
+            (5, Ok(entry(Some("SHA256"), "b", false))),
+        ]);
+    }
+}
@@ -0,0 +1,47 @@
+//! Comparisons whose timing doesn't depend on the data, for verifying digests and MACs.
+//! A plain `==` stops at the first differing byte, which tells an attacker how much of a forged tag is right.
+
+use std::hint::black_box;
+
+/// Compares two byte slices in constant time.
+///
+/// Every byte is visited: the differences are OR-ed into an accumulator that is only inspected at the end,
+/// there is no early exit on the first mismatch. The lengths aren't secret, slices of different lengths
+/// are unequal right away.
+pub fn eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    let mut diff: u8 = 0;
+    for i in 0..a.len() {
+        diff |= a[i] ^ b[i];
+    }
+
+    // Keeps the compiler from turning the loop back into a short-circuiting comparison
+    black_box(diff) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eq() {
+        let a = [0x5au8; 64];
+        assert!(eq(&a, &a.clone()));
+        assert!(eq(b"", b""));
+
+        // A difference in any position, and in any bit, is found
+        for i in 0..a.len() {
+            for bit in 0..8 {
+                let mut b = a;
+                b[i] ^= 1 << bit;
+                assert!(!eq(&a, &b));
+            }
+        }
+
+        assert!(!eq(&a, &a[..63]));
+        assert!(!eq(b"", b"\0"));
+    }
+}
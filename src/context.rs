@@ -0,0 +1,335 @@
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::mem::size_of;
+
+use num::traits::ToBytes;
+
+use crate::algorithm::Algorithm;
+use crate::hasher::{Hasher, Sha2Params, Word};
+
+// Big enough for the 1024bits blocks, the 512bits variants only use the first half
+const MAX_BLOCK_BYTES: usize = 128;
+
+// Streams are read through this stack buffer so memory stays constant whatever their size
+pub(crate) const READ_BUFFER_BYTES: usize = 16 * 1024;
+
+// Exported state layout, integers are big endian:
+// magic "SHA2" | version (1 byte) | word size (1 byte) | output size (1 byte) | message length in bytes (16 bytes)
+// | chaining value (8 words) | buffered tail length (1 byte) | buffered tail
+// The word and output sizes identify the variant so a state can't be resumed with another one,
+// SHA-512/t states record t / 8 as their output size.
+const STATE_MAGIC: &[u8; 4] = b"SHA2";
+const STATE_VERSION: u8 = 1;
+const STATE_HEADER_BYTES: usize = 4 + 1 + 1 + 1 + 16;
+
+/// Returned by [`Context::import_state`] when the bytes aren't a state exported by the same variant
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidState;
+
+impl fmt::Display for InvalidState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid or incompatible SHA-2 context state")
+    }
+}
+
+impl std::error::Error for InvalidState {}
+
+/// Streaming context, for messages that don't fit in memory or arrive in chunks.
+/// Each algorithm module exposes it for its variant as `Context`.
+/// ```
+/// use sha::two_five_six::{hash, Context};
+///
+/// let mut ctx = Context::new();
+/// ctx.update(b"Hello, ");
+/// ctx.update(b"World!");
+/// assert_eq!(ctx.finalize(), hash(b"Hello, World!"));
+/// ```
+#[derive(Clone)]
+pub struct Context<P: Sha2Params> {
+    hasher: Hasher<P>,
+    h: [P::Word; 8],
+    // Holds the start of a block until enough bytes arrive to compress it
+    buffer: [u8; MAX_BLOCK_BYTES],
+    buffer_len: usize,
+    msg_len: u128, // <-- in bytes
+}
+
+impl<P: Sha2Params> Context<P> {
+    pub fn new() -> Self {
+        Self::with_initial_hash(P::H0)
+    }
+
+    // SHA-512/t uses the SHA-512 parameters with an initial hash value computed at runtime
+    pub(crate) fn with_initial_hash(h: [P::Word; 8]) -> Self {
+        Self {
+            hasher: Hasher::new(),
+            h,
+            buffer: [0; MAX_BLOCK_BYTES],
+            buffer_len: 0,
+            msg_len: 0,
+        }
+    }
+
+    // Resumes right after msg_len bytes (a whole number of blocks) that led to the chaining value h
+    pub(crate) fn with_chaining_value(h: [P::Word; 8], msg_len: u128) -> Self {
+        debug_assert!(msg_len.is_multiple_of(P::Word::BLOCK_BYTES as u128));
+        let mut ctx = Self::with_initial_hash(h);
+        ctx.msg_len = msg_len;
+        ctx
+    }
+
+    fn process_block(&mut self, bytes: &[u8]) {
+        let block = P::Word::read_block(bytes);
+        self.hasher.process_block(&mut self.h, block);
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        let block_bytes = P::Word::BLOCK_BYTES;
+        let mut data = data;
+        self.msg_len = self.msg_len.wrapping_add(data.len() as u128);
+
+        // Complete the pending block first
+        if self.buffer_len > 0 {
+            let n = usize::min(block_bytes - self.buffer_len, data.len());
+            self.buffer[self.buffer_len..self.buffer_len + n].copy_from_slice(&data[..n]);
+            self.buffer_len += n;
+            data = &data[n..];
+
+            if self.buffer_len < block_bytes {
+                return;
+            }
+
+            let buffer = self.buffer;
+            self.process_block(&buffer[..block_bytes]);
+            self.buffer_len = 0;
+        }
+
+        // Full blocks are read straight from the input
+        let mut chunks = data.chunks_exact(block_bytes);
+        for chunk in &mut chunks {
+            self.process_block(chunk);
+        }
+
+        let rest = chunks.remainder();
+        self.buffer[..rest.len()].copy_from_slice(rest);
+        self.buffer_len = rest.len();
+    }
+
+    /// Feeds everything the reader yields until EOF, returns the number of bytes read
+    pub fn update_reader<R: Read>(&mut self, mut reader: R) -> io::Result<u64> {
+        let mut buffer = [0u8; READ_BUFFER_BYTES];
+        let mut total: u64 = 0;
+
+        loop {
+            match reader.read(&mut buffer) {
+                Ok(0) => return Ok(total),
+                Ok(n) => {
+                    self.update(&buffer[..n]);
+                    total += n as u64;
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Serializes the progress of the hash (chaining value, bytes processed and buffered tail)
+    /// so it can be resumed later, possibly in another process, with [`Context::import_state`].
+    /// The chaining value reveals as much about the message as the hash does, store it accordingly.
+    pub fn export_state(&self) -> Vec<u8> {
+        self.export_state_with_output(P::OUTPUT_BYTES)
+    }
+
+    // Exports with another output size in the header, SHA-512/t contexts record t / 8 there
+    pub(crate) fn export_state_with_output(&self, output_bytes: usize) -> Vec<u8> {
+        let word_bytes = size_of::<P::Word>();
+        let mut state = Vec::with_capacity(STATE_HEADER_BYTES + 8 * word_bytes + 1 + self.buffer_len);
+
+        state.extend_from_slice(STATE_MAGIC);
+        state.push(STATE_VERSION);
+        state.push(word_bytes as u8);
+        state.push(output_bytes as u8);
+        state.extend_from_slice(&self.msg_len.to_be_bytes());
+        for word in self.h {
+            state.extend_from_slice(word.to_be_bytes().as_ref());
+        }
+        state.push(self.buffer_len as u8);
+        state.extend_from_slice(&self.buffer[..self.buffer_len]);
+
+        state
+    }
+
+    /// Resumes a hash from the output of [`Context::export_state`]
+    pub fn import_state(state: &[u8]) -> Result<Self, InvalidState> {
+        match Self::import_state_with_output(state)? {
+            (ctx, output_bytes) if output_bytes == P::OUTPUT_BYTES => Ok(ctx),
+            _ => Err(InvalidState),
+        }
+    }
+
+    // Imports a state of the same word size whatever its output size, which is returned for the caller to check
+    pub(crate) fn import_state_with_output(state: &[u8]) -> Result<(Self, usize), InvalidState> {
+        let word_bytes = size_of::<P::Word>();
+        let block_bytes = P::Word::BLOCK_BYTES;
+        let tail_start = STATE_HEADER_BYTES + 8 * word_bytes + 1;
+
+        if state.len() < tail_start
+            || &state[..4] != STATE_MAGIC
+            || state[4] != STATE_VERSION
+            || state[5] as usize != word_bytes {
+            return Err(InvalidState);
+        }
+
+        let msg_len = u128::from_be_bytes(state[7..STATE_HEADER_BYTES].try_into().unwrap());
+        let mut h = P::H0;
+        for i in 0..8 {
+            h[i] = P::Word::read_word(&state[STATE_HEADER_BYTES + i * word_bytes..]);
+        }
+
+        // Full blocks are always compressed, so the tail is what's left of the message length
+        let buffer_len = state[tail_start - 1] as usize;
+        if buffer_len >= block_bytes
+            || buffer_len as u128 != msg_len % block_bytes as u128
+            || state.len() != tail_start + buffer_len {
+            return Err(InvalidState);
+        }
+
+        let mut ctx = Self::with_initial_hash(h);
+        ctx.buffer[..buffer_len].copy_from_slice(&state[tail_start..]);
+        ctx.buffer_len = buffer_len;
+        ctx.msg_len = msg_len;
+        Ok((ctx, state[6] as usize))
+    }
+
+    pub fn finalize(self) -> P::Output {
+        self.finalize_bits(0, 0)
+    }
+
+    // Ends a message that doesn't stop on a byte boundary: the last num_bits bits (less than 8)
+    // are the upper bits of last_bits
+    pub fn finalize_bits(mut self, last_bits: u8, num_bits: u32) -> P::Output {
+        assert!(num_bits < 8, "at most 7 trailing bits, got {num_bits}");
+
+        // The buffer always has room for one more byte since full blocks are compressed right away
+        let mut tail_len = self.buffer_len;
+        if num_bits > 0 {
+            self.buffer[tail_len] = last_bits;
+            tail_len += 1;
+        }
+
+        let num_bits_msg = self.msg_len.wrapping_mul(8).wrapping_add(num_bits as u128);
+        let tail = P::Word::pad_tail(&self.buffer[..tail_len], num_bits_msg);
+        for block in tail.blocks() {
+            self.hasher.process_block(&mut self.h, *block);
+        }
+
+        self.hasher.output(self.h)
+    }
+}
+
+impl<P: Sha2Params> Default for Context<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Allows io::copy(&mut file, &mut ctx)
+impl<P: Sha2Params> Write for Context<P> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<P: Sha2Params> Algorithm for Context<P> {
+    const BLOCK_BYTES: usize = P::Word::BLOCK_BYTES;
+    const OUTPUT_BYTES: usize = P::OUTPUT_BYTES;
+
+    type Output = P::Output;
+
+    fn update(&mut self, data: &[u8]) {
+        Context::update(self, data);
+    }
+
+    fn update_reader<R: Read>(&mut self, reader: R) -> io::Result<u64> {
+        Context::update_reader(self, reader)
+    }
+
+    fn finalize(self) -> Self::Output {
+        Context::finalize(self)
+    }
+
+    fn finalize_bits(self, last_bits: u8, num_bits: u32) -> Self::Output {
+        Context::finalize_bits(self, last_bits, num_bits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{two_two_four, two_five_six, three_eight_four, five_twelve, five_twelve_t};
+
+    // Exports the state after every split point, resumes from it and checks the final hash
+    fn check_round_trip<P: Sha2Params>(msg: &[u8], expected: P::Output) {
+        for split in [0, 1, 55, 63, 64, 65, 111, 127, 128, 129, 300, msg.len()] {
+            let mut ctx: Context<P> = Context::new();
+            ctx.update(&msg[..split]);
+            let state = ctx.export_state();
+
+            let mut resumed: Context<P> = Context::import_state(&state).unwrap();
+            assert_eq!(resumed.export_state(), state);
+            resumed.update(&msg[split..]);
+            assert_eq!(resumed.finalize().as_ref(), expected.as_ref());
+        }
+    }
+
+    #[test]
+    fn test_state_round_trip() {
+        let msg: Vec<u8> = (0..1000u32).map(|i| (i * 7 % 256) as u8).collect();
+
+        check_round_trip::<two_two_four::Sha224>(&msg, two_two_four::hash(&msg));
+        check_round_trip::<two_five_six::Sha256>(&msg, two_five_six::hash(&msg));
+        check_round_trip::<three_eight_four::Sha384>(&msg, three_eight_four::hash(&msg));
+        check_round_trip::<five_twelve::Sha512>(&msg, five_twelve::hash(&msg));
+        check_round_trip::<five_twelve_t::Sha512_256>(&msg, five_twelve_t::hash_256(&msg));
+    }
+
+    #[test]
+    fn test_state_layout() {
+        let mut ctx = two_five_six::Context::new();
+        ctx.update(b"abc");
+        let state = ctx.export_state();
+
+        assert_eq!(&state[..7], b"SHA2\x01\x04\x20");
+        assert_eq!(state[7..23], 3u128.to_be_bytes());
+        assert_eq!(state[23..27], two_five_six::Sha256::H0[0].to_be_bytes());
+        assert_eq!(&state[55..], b"\x03abc");
+    }
+
+    #[test]
+    fn test_invalid_state() {
+        let mut ctx = five_twelve::Context::new();
+        ctx.update(b"abc");
+        let state = ctx.export_state();
+
+        // Another variant, even with the same word size
+        assert!(two_five_six::Context::import_state(&state).is_err());
+        assert!(three_eight_four::Context::import_state(&state).is_err());
+
+        let mut bad_version = state.clone();
+        bad_version[4] = 2;
+        assert_eq!(five_twelve::Context::import_state(&bad_version).err(), Some(InvalidState));
+
+        // Truncated, trailing garbage, and a tail that doesn't match the message length
+        assert!(five_twelve::Context::import_state(&state[..state.len() - 1]).is_err());
+        assert!(five_twelve::Context::import_state(&[&state[..], b"d"].concat()).is_err());
+        let mut bad_len = state.clone();
+        bad_len[22] = 4;
+        assert!(five_twelve::Context::import_state(&bad_len).is_err());
+        assert!(five_twelve::Context::import_state(b"").is_err());
+    }
+}
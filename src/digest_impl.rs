@@ -0,0 +1,143 @@
+//! RustCrypto `digest` traits for the SHA-256 and SHA-512 contexts (`digest` feature),
+//! so they can be used with `hmac::SimpleHmac`, `hkdf::SimpleHkdf` or any code generic over `Digest`.
+
+use digest::consts::{U128, U32, U64};
+use digest::{FixedOutput, FixedOutputReset, HashMarker, Output, OutputSizeUser, Reset, Update};
+use digest::core_api::BlockSizeUser;
+
+use crate::two_five_six;
+use crate::five_twelve;
+
+impl HashMarker for two_five_six::Context {}
+
+impl OutputSizeUser for two_five_six::Context {
+    type OutputSize = U32;
+}
+
+impl BlockSizeUser for two_five_six::Context {
+    type BlockSize = U64;
+}
+
+impl Update for two_five_six::Context {
+    fn update(&mut self, data: &[u8]) {
+        two_five_six::Context::update(self, data);
+    }
+}
+
+impl FixedOutput for two_five_six::Context {
+    fn finalize_into(self, out: &mut Output<Self>) {
+        out.copy_from_slice(&two_five_six::Context::finalize(self));
+    }
+}
+
+impl Reset for two_five_six::Context {
+    fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+impl FixedOutputReset for two_five_six::Context {
+    fn finalize_into_reset(&mut self, out: &mut Output<Self>) {
+        let ctx = std::mem::take(self);
+        out.copy_from_slice(&ctx.finalize());
+    }
+}
+
+impl HashMarker for five_twelve::Context {}
+
+impl OutputSizeUser for five_twelve::Context {
+    type OutputSize = U64;
+}
+
+impl BlockSizeUser for five_twelve::Context {
+    type BlockSize = U128;
+}
+
+impl Update for five_twelve::Context {
+    fn update(&mut self, data: &[u8]) {
+        five_twelve::Context::update(self, data);
+    }
+}
+
+impl FixedOutput for five_twelve::Context {
+    fn finalize_into(self, out: &mut Output<Self>) {
+        out.copy_from_slice(&five_twelve::Context::finalize(self));
+    }
+}
+
+impl Reset for five_twelve::Context {
+    fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+impl FixedOutputReset for five_twelve::Context {
+    fn finalize_into_reset(&mut self, out: &mut Output<Self>) {
+        let ctx = std::mem::take(self);
+        out.copy_from_slice(&ctx.finalize());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use digest::{Digest, Mac};
+
+    use crate::hkdf;
+    use crate::hmac;
+    use crate::two_five_six;
+    use crate::five_twelve;
+
+    // Only uses the traits, like code written for any RustCrypto hash would
+    fn generic_digest<D: Digest>(chunks: &[&[u8]]) -> Vec<u8> {
+        let mut hasher = D::new();
+        for chunk in chunks {
+            Digest::update(&mut hasher, chunk);
+        }
+        Digest::finalize(hasher).to_vec()
+    }
+
+    #[test]
+    fn test_digest() {
+        let msg = b"Lorem ipsum dolor sit amet, consectetuer adipiscing elit. Aenean commodo ligula eget dolor.";
+        let chunks: Vec<&[u8]> = msg.chunks(7).collect();
+
+        assert_eq!(generic_digest::<two_five_six::Context>(&chunks), two_five_six::hash(msg).to_vec());
+        assert_eq!(generic_digest::<five_twelve::Context>(&chunks), five_twelve::hash(msg).to_vec());
+        assert_eq!(<two_five_six::Context as Digest>::digest(msg).as_slice(), &two_five_six::hash(msg));
+        assert_eq!(<two_five_six::Context as Digest>::output_size(), 32);
+        assert_eq!(<five_twelve::Context as Digest>::output_size(), 64);
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut hasher = <five_twelve::Context as Digest>::new();
+        Digest::update(&mut hasher, b"discarded");
+        Digest::reset(&mut hasher);
+        Digest::update(&mut hasher, b"Hello, World!");
+        assert_eq!(hasher.finalize_reset().as_slice(), &five_twelve::hash(b"Hello, World!"));
+        assert_eq!(Digest::finalize(hasher).as_slice(), &five_twelve::hash(b""));
+    }
+
+    #[test]
+    fn test_rustcrypto_hmac() {
+        let mut mac = <::hmac::SimpleHmac<two_five_six::Context> as Mac>::new_from_slice(b"key").unwrap();
+        mac.update(b"The quick brown fox jumps over the lazy dog");
+        let tag = mac.finalize().into_bytes();
+        assert_eq!(tag.as_slice(), &hmac::hmac::<two_five_six::Context>(b"key", b"The quick brown fox jumps over the lazy dog"));
+
+        let mut mac = <::hmac::SimpleHmac<five_twelve::Context> as Mac>::new_from_slice(&[0xaa; 200]).unwrap();
+        mac.update(b"message");
+        assert_eq!(mac.finalize().into_bytes().as_slice(), &hmac::hmac::<five_twelve::Context>(&[0xaa; 200], b"message"));
+    }
+
+    #[test]
+    fn test_rustcrypto_hkdf() {
+        let ikm = [0x0b; 22];
+        let salt = [0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c];
+        let info = [0xf0, 0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8, 0xf9];
+
+        let mut okm = [0u8; 42];
+        ::hkdf::SimpleHkdf::<two_five_six::Context>::new(Some(&salt), &ikm).expand(&info, &mut okm).unwrap();
+        assert_eq!(okm.to_vec(), hkdf::derive::<two_five_six::Context>(&salt, &ikm, &info, 42).unwrap());
+    }
+}
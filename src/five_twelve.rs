@@ -1,7 +1,11 @@
-mod constants;
+pub(crate) mod constants;
 
-use crate::preprocessing;
-use crate::hasher::Hasher;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::constant_time;
+use crate::hasher::{split_bits, Hasher, Sha2Params};
 
 // Shadows f64::constants::SQRT_X from std library
 use constants::SQRT_2;
@@ -14,70 +18,97 @@ use constants::SQRT_17;
 use constants::SQRT_19;
 
 #[inline(always)]
-fn sig_lc_0(x: u64) -> u64 {
+pub(crate) fn sig_lc_0(x: u64) -> u64 {
     x.rotate_right(1) ^ x.rotate_right(8) ^ x >> 7
 }
 
 #[inline(always)]
-fn sig_lc_1(x: u64) -> u64 {
+pub(crate) fn sig_lc_1(x: u64) -> u64 {
     x.rotate_right(19) ^ x.rotate_right(61) ^ x >> 6
 }
 
 #[inline(always)]
-fn sig_uc_0(x: u64) -> u64 {
+pub(crate) fn sig_uc_0(x: u64) -> u64 {
     x.rotate_right(28) ^ x.rotate_right(34) ^ x.rotate_right(39)
 }
 
 #[inline(always)]
-fn sig_uc_1(x: u64) -> u64 {
+pub(crate) fn sig_uc_1(x: u64) -> u64 {
     x.rotate_right(14) ^ x.rotate_right(18) ^ x.rotate_right(41)
 }
 
 const FINAL_HASH_BYTES: usize = 64; // <-- 512 bits in bytes
 
+// Initial hash value
+pub(crate) const H0: [u64; 8] = [
+    SQRT_2, SQRT_3, SQRT_5,
+    SQRT_7, SQRT_11, SQRT_13, 
+    SQRT_17, SQRT_19
+    ];
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Sha512;
+
+impl Sha2Params for Sha512 {
+    type Word = u64;
+    type Output = [u8; FINAL_HASH_BYTES];
+
+    const ROUNDS: usize = 80;
+    const K: &'static [u64] = &constants::K;
+    const H0: [u64; 8] = H0;
+    const OUTPUT_BYTES: usize = FINAL_HASH_BYTES;
+
+    fn sig_lc_0(x: u64) -> u64 { sig_lc_0(x) }
+    fn sig_lc_1(x: u64) -> u64 { sig_lc_1(x) }
+    fn sig_uc_0(x: u64) -> u64 { sig_uc_0(x) }
+    fn sig_uc_1(x: u64) -> u64 { sig_uc_1(x) }
+}
+
+/// Streaming SHA-512 context, see [`crate::Context`]
+pub type Context = crate::context::Context<Sha512>;
+
 pub fn hash(message: &[u8]) -> [u8;FINAL_HASH_BYTES] {
-        
-    let blocks = preprocessing::blockify_msg_1024(message);
-
-    // Initial hash value
-    let h: [u64; 8] = [
-        SQRT_2, SQRT_3, SQRT_5,
-        SQRT_7, SQRT_11, SQRT_13, 
-        SQRT_17, SQRT_19
-        ];
-    let hasher: Hasher<u64, 64, 80> = Hasher::new(constants::K);
-    let h = hasher.hash(h, blocks, sig_lc_0, sig_lc_1, sig_uc_0, sig_uc_1);
-
-    // Prepare the final hash as a byte array
-    let mut hash: [u8; FINAL_HASH_BYTES] = [0; FINAL_HASH_BYTES];
-    for i in 0..8 {
-        let bytes = u64::to_be_bytes(h[i]);
-        hash[i*8] = bytes[0];
-        hash[i*8+1] = bytes[1];
-        hash[i*8+2] = bytes[2];
-        hash[i*8+3] = bytes[3];
-        hash[i*8+4] = bytes[4];
-        hash[i*8+5] = bytes[5];
-        hash[i*8+6] = bytes[6];
-        hash[i*8+7] = bytes[7];
-    }
-    
-    hash
+    Hasher::<Sha512>::new().digest(message, 0)
+}
+
+/// Hashes the first num_bits bits of message, for messages that don't end on a byte boundary.
+/// Bits are read MSB first, the unused low bits of the last byte are ignored
+///
+/// # Panics
+///
+/// If num_bits is more than the bits of message
+pub fn hash_bits(message: &[u8], num_bits: u64) -> [u8;FINAL_HASH_BYTES] {
+    let (message, trailing_bits) = split_bits(message, num_bits);
+    Hasher::<Sha512>::new().digest(message, trailing_bits)
+}
+
+// Checks the hash of message against expected in constant time, for digests used as tokens
+pub fn verify(message: &[u8], expected: &[u8;FINAL_HASH_BYTES]) -> bool {
+    constant_time::eq(&hash(message), expected)
+}
+
+// Hashes everything the reader yields until EOF, through a fixed size buffer
+pub fn hash_reader<R: Read>(reader: R) -> io::Result<[u8;FINAL_HASH_BYTES]> {
+    let mut ctx = Context::new();
+    ctx.update_reader(reader)?;
+    Ok(ctx.finalize())
+}
+
+pub fn hash_file<P: AsRef<Path>>(path: P) -> io::Result<[u8;FINAL_HASH_BYTES]> {
+    hash_reader(File::open(path)?)
+}
+
+#[cfg(feature = "tokio")]
+pub async fn hash_async_reader<R: tokio::io::AsyncRead + Unpin>(reader: R) -> io::Result<[u8;FINAL_HASH_BYTES]> {
+    crate::async_io::hash_async_reader::<Context, R>(reader).await
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    // Utility function
     fn hash_to_str(hash: [u8; 64]) -> String {
-        let mut hash_str = String::from("");
-    
-        for i in 0..64 {
-            hash_str += &format!("{:02x?}", hash[i]);
-        }
-    
-        return hash_str;
+        crate::Digest::from(hash).to_string()
     }
 
     #[test]
@@ -87,4 +118,89 @@ mod tests {
         assert_eq!(hash_to_str(hash(b"Lorem ipsum dolor sit amet, consectetuer adipiscing elit. Aenean commodo ligula eget dolor. Aenean massa. Cum sociis natoque penatibus et magnis dis parturient montes, nascetur ridiculus mus. Donec quam felis, ultricies nec, pellentesque eu, pretium quis, sem. Nulla consequat massa quis enim. Donec pede justo, fringilla vel, aliquet nec, vulputate eget, arcu. In enim justo, rhoncus ut, imperdiet a, venenatis vitae, justo. Nullam dictum felis eu pede mollis pretium. Integer tincidunt. Cras dapibus. Vivamus elementum semper nisi. Aenean vulputate eleifend tellus. Aenean leo ligula, porttitor eu, consequat vitae, eleifend ac, enim. Aliquam lorem ante, dapibus in, viverra quis, feugiat a, tellus. Phasellus viverra nulla ut metus varius laoreet. Quisque rutrum. Aenean imperdiet. Etiam ultricies nisi vel augue. Curabitur ullamcorper ultricies nisi. Nam eget dui. Etiam rhoncus. Maecenas tempus, tellus eget condimentum rhoncus, sem quam semper libero, sit amet adipiscing sem neque sed ipsum. Nam quam nunc, blandit vel, luctus pulvinar, hendrerit id, lorem. Maecenas nec odio et ante tincidunt tempus. Donec vitae sapien ut libero venenatis faucibus. Nullam quis ante. Etiam sit amet orci eget eros faucibus tincidunt. Duis leo. Sed fringilla mauris sit amet nibh. Donec sodales sagittis magna. Sed consequat, leo eget bibendum sodales, augue velit cursus nunc,")), "32dc3fd7d262ec2a9912e45f009fe61f572093e04f23157c5bfc4b84535ee35be12e4504dd7e211f0832220df65e3d629e441b1726ef31f0a6bfd3531646bfab");
         assert_eq!(hash_to_str(hash(b"It is a long established fact that a reader will be distracted by the readable content of a page when looking at its layout. The point of using Lorem Ipsum is that it has a more-or-less normal distribution of letters, as opposed to using 'Content here, content here', making it look like readable English. Many desktop publishing packages and web page editors now use Lorem Ipsum as their default model text, and a search for 'lorem ipsum' will uncover many web sites still in their infancy. Various versions have evolved over the years, sometimes by accident, sometimes on purpose (injected humour and the like).")), "6d96ae87d4e9ef63c1246c9073e3888ec3821b888b13b93c2d95d8fd447d28286f3c863d119955ec52111f82c2f0b46c158c2edf57ab8f7d61eac88e8bd50f87");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_context() {
+        let msg: Vec<u8> = (0..1000u32).map(|i| (i * 7 % 256) as u8).collect();
+
+        // Every chunk size must give the same result as the one-shot function, including across block boundaries
+        for chunk_size in [1, 3, 111, 112, 127, 128, 129, 256, 1000] {
+            let mut ctx = Context::new();
+            for chunk in msg.chunks(chunk_size) {
+                ctx.update(chunk);
+            }
+            assert_eq!(ctx.finalize(), hash(&msg));
+        }
+
+        // Padding edge cases
+        for len in [0, 111, 112, 127, 128, 239, 240] {
+            let mut ctx = Context::new();
+            ctx.update(&msg[..len]);
+            assert_eq!(ctx.finalize(), hash(&msg[..len]));
+        }
+    }
+
+    #[test]
+    fn test_hash_bits() {
+        // RFC 6234 test with 5 bits
+        assert_eq!(hash_to_str(hash_bits(&[0xb0], 5)), "d4ee29a9e90985446b913cf1d1376c836f4be2c1cf3cada0720a6bf4857d886a7ecb3c4e4c0fa8c7f95214e41dc1b0d21b22a84cc03bf8ce4845f34dd5bdbad4");
+
+        // Boundary vectors computed with an independent bit oriented implementation
+        let msg: Vec<u8> = (0..130u32).map(|i| ((i * 37 + 11) % 256) as u8).collect();
+        assert_eq!(hash_to_str(hash_bits(&msg, 895)), "f68fe1f169575909d7edecdafbb1400faa27a394fe06d59552e5137318955dad340a209c2f863c7ccc050a158b8b68ab507d66f50edd09f18a4d5b77c08ad307");
+        assert_eq!(hash_to_str(hash_bits(&msg, 897)), "e71d7088b155847d0cc16da873c895ed1be76f93494512735a2cf92aa9499bb069b3ea5a5ce57acd827b422df6d04d03b853fb6923512341607a97aecb0431aa");
+        assert_eq!(hash_to_str(hash_bits(&msg, 1023)), "9765594ebfec45973d0cf8413da8551a84c6db3630814d7e2d8f4b0e924984d8449fa4e91165b83bd5d2e969c48f6beb03cfa473f878142c386a7c58385c25e1");
+
+        // Byte aligned lengths are plain hashes
+        assert_eq!(hash_bits(&msg, 800), hash(&msg[..100]));
+        assert_eq!(hash_bits(&msg, 0), hash(b""));
+
+        // The streaming path gives the same result
+        for num_bits in [5, 895, 897, 1023] {
+            let mut ctx = Context::new();
+            ctx.update(&msg[..num_bits / 8]);
+            assert_eq!(ctx.finalize_bits(msg[num_bits / 8], (num_bits % 8) as u32), hash_bits(&msg, num_bits as u64));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "num_bits is 9 but the message only has 8 bits")]
+    fn test_hash_bits_too_long() {
+        hash_bits(&[0], 9);
+    }
+
+    #[test]
+    fn test_hash_reader() {
+        let msg: Vec<u8> = (0..100_000u32).map(|i| (i * 31 % 251) as u8).collect();
+
+        assert_eq!(hash_reader(io::Cursor::new(&msg)).unwrap(), hash(&msg));
+        assert_eq!(hash_reader(io::empty()).unwrap(), hash(b""));
+
+        let mut ctx = Context::new();
+        assert_eq!(io::copy(&mut &msg[..], &mut ctx).unwrap(), msg.len() as u64);
+        assert_eq!(ctx.finalize(), hash(&msg));
+    }
+
+    #[test]
+    fn test_hash_file() {
+        let msg: Vec<u8> = (0..70_000u32).map(|i| (i * 17 % 256) as u8).collect();
+        let path = std::env::temp_dir().join(format!("sha_test_hash_file_five_twelve_{}", std::process::id()));
+        std::fs::write(&path, &msg).unwrap();
+        let result = hash_file(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(result.unwrap(), hash(&msg));
+        assert_eq!(hash_file(path).unwrap_err().kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_verify() {
+        let mut expected = hash(b"Hello, World!");
+        assert!(verify(b"Hello, World!", &expected));
+        assert!(<Context as crate::algorithm::Algorithm>::verify(b"Hello, World!", &expected));
+        assert!(!verify(b"Hello, World?", &expected));
+
+        expected[0] ^= 1;
+        assert!(!verify(b"Hello, World!", &expected));
+    }
+}
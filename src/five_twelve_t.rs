@@ -0,0 +1,272 @@
+//! SHA-512/t: SHA-512 with a different initial hash value, truncated to t bits.
+//! ```
+//! use sha::five_twelve_t::{hash_256, sha512_t};
+//!
+//! let hash_result: [u8; 32] = hash_256(b"Hello, World!");
+//! assert_eq!(sha512_t(256).hash(b"Hello, World!"), hash_result.to_vec());
+//! ```
+
+mod constants;
+
+use std::io::{self, Read, Write};
+
+use crate::constant_time;
+use crate::context::InvalidState;
+use crate::hasher::{Hasher, Sha2Params};
+use crate::five_twelve;
+use crate::five_twelve::{sig_lc_0, sig_lc_1, sig_uc_0, sig_uc_1};
+
+const HASH_224_BYTES: usize = 28;
+const HASH_256_BYTES: usize = 32;
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Sha512_224;
+
+impl Sha2Params for Sha512_224 {
+    type Word = u64;
+    type Output = [u8; HASH_224_BYTES];
+
+    const ROUNDS: usize = 80;
+    const K: &'static [u64] = &five_twelve::constants::K;
+    const H0: [u64; 8] = constants::H0_224;
+    const OUTPUT_BYTES: usize = HASH_224_BYTES;
+
+    fn sig_lc_0(x: u64) -> u64 { sig_lc_0(x) }
+    fn sig_lc_1(x: u64) -> u64 { sig_lc_1(x) }
+    fn sig_uc_0(x: u64) -> u64 { sig_uc_0(x) }
+    fn sig_uc_1(x: u64) -> u64 { sig_uc_1(x) }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Sha512_256;
+
+impl Sha2Params for Sha512_256 {
+    type Word = u64;
+    type Output = [u8; HASH_256_BYTES];
+
+    const ROUNDS: usize = 80;
+    const K: &'static [u64] = &five_twelve::constants::K;
+    const H0: [u64; 8] = constants::H0_256;
+    const OUTPUT_BYTES: usize = HASH_256_BYTES;
+
+    fn sig_lc_0(x: u64) -> u64 { sig_lc_0(x) }
+    fn sig_lc_1(x: u64) -> u64 { sig_lc_1(x) }
+    fn sig_uc_0(x: u64) -> u64 { sig_uc_0(x) }
+    fn sig_uc_1(x: u64) -> u64 { sig_uc_1(x) }
+}
+
+/// Streaming SHA-512/224 context, see [`crate::Context`]
+pub type Context224 = crate::context::Context<Sha512_224>;
+
+/// Streaming SHA-512/256 context, see [`crate::Context`]
+pub type Context256 = crate::context::Context<Sha512_256>;
+
+pub fn hash_224(message: &[u8]) -> [u8; HASH_224_BYTES] {
+    Hasher::<Sha512_224>::new().digest(message, 0)
+}
+
+pub fn hash_256(message: &[u8]) -> [u8; HASH_256_BYTES] {
+    Hasher::<Sha512_256>::new().digest(message, 0)
+}
+
+// Constant time checks of the hash of message
+pub fn verify_224(message: &[u8], expected: &[u8; HASH_224_BYTES]) -> bool {
+    constant_time::eq(&hash_224(message), expected)
+}
+
+pub fn verify_256(message: &[u8], expected: &[u8; HASH_256_BYTES]) -> bool {
+    constant_time::eq(&hash_256(message), expected)
+}
+
+/// SHA-512/t for an arbitrary t, with the initial hash value derived by the FIPS 180-4 generation function
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Sha512T {
+    t: usize,
+    h: [u64; 8],
+}
+
+/// Builds SHA-512/t. The initial hash value is the SHA-512 of the string "SHA-512/t"
+/// computed with the SHA-512 initial hash value XORed with 0xa5a5a5a5a5a5a5a5.
+///
+/// Panics if t isn't a multiple of 8 in 8..512 or if t is 384 (which is forbidden by the standard).
+pub fn sha512_t(t: usize) -> Sha512T {
+    assert!(t > 0 && t < 512 && t.is_multiple_of(8), "SHA-512/t requires t to be a multiple of 8 between 8 and 504, got {t}");
+    assert!(t != 384, "SHA-512/384 is not allowed, use SHA-384");
+
+    let mut iv = five_twelve::H0;
+    for word in iv.iter_mut() {
+        *word ^= constants::IV_GEN_MASK;
+    }
+
+    let mut ctx = five_twelve::Context::with_initial_hash(iv);
+    ctx.update(format!("SHA-512/{t}").as_bytes());
+    let hash = ctx.finalize();
+
+    let mut h: [u64; 8] = [0; 8];
+    for i in 0..8 {
+        h[i] = u64::from_be_bytes(hash[i*8..i*8+8].try_into().unwrap());
+    }
+
+    Sha512T { t, h }
+}
+
+impl Sha512T {
+    // Output size in bytes
+    pub fn output_len(&self) -> usize {
+        self.t / 8
+    }
+
+    pub fn hash(&self, message: &[u8]) -> Vec<u8> {
+        let hasher: Hasher<five_twelve::Sha512> = Hasher::new();
+        let h = hasher.hash(self.h, message, 0);
+        hasher.output(h)[..self.output_len()].to_vec()
+    }
+
+    pub fn verify(&self, message: &[u8], expected: &[u8]) -> bool {
+        constant_time::eq(&self.hash(message), expected)
+    }
+
+    pub fn context(&self) -> Context {
+        Context { inner: five_twelve::Context::with_initial_hash(self.h), num_bytes: self.output_len() }
+    }
+}
+
+/// Streaming SHA-512/t context, see [`five_twelve::Context`]
+pub struct Context {
+    inner: five_twelve::Context,
+    num_bytes: usize,
+}
+
+impl Context {
+    pub fn update(&mut self, data: &[u8]) {
+        self.inner.update(data);
+    }
+
+    /// See [`crate::Context::export_state`], t is part of the state
+    pub fn export_state(&self) -> Vec<u8> {
+        self.inner.export_state_with_output(self.num_bytes)
+    }
+
+    /// Resumes a SHA-512/t hash exported by [`Context::export_state`], with the t it was created with.
+    /// States of the fixed SHA-512/224 and SHA-512/256 contexts are accepted as well since they are the same functions.
+    pub fn import_state(state: &[u8]) -> Result<Self, InvalidState> {
+        let (inner, num_bytes) = five_twelve::Context::import_state_with_output(state)?;
+        // Same t as sha512_t accepts, SHA-384 and SHA-512 states end up here
+        if num_bytes == 0 || num_bytes >= 64 || num_bytes == 48 {
+            return Err(InvalidState);
+        }
+
+        Ok(Context { inner, num_bytes })
+    }
+
+    /// See [`crate::Context::update_reader`]
+    pub fn update_reader<R: Read>(&mut self, reader: R) -> io::Result<u64> {
+        self.inner.update_reader(reader)
+    }
+
+    pub fn finalize(self) -> Vec<u8> {
+        self.finalize_bits(0, 0)
+    }
+
+    pub fn finalize_bits(self, last_bits: u8, num_bits: u32) -> Vec<u8> {
+        let mut hash = self.inner.finalize_bits(last_bits, num_bits).to_vec();
+        hash.truncate(self.num_bytes);
+        hash
+    }
+}
+
+impl Write for Context {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::to_hex;
+
+    const MSG_896: &[u8] = b"abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu";
+
+    #[test]
+    fn test_iv_generation() {
+        assert_eq!(sha512_t(224).h, constants::H0_224);
+        assert_eq!(sha512_t(256).h, constants::H0_256);
+    }
+
+    // Examples from https://csrc.nist.gov/projects/cryptographic-standards-and-guidelines/example-values
+    #[test]
+    fn test_hash() {
+        assert_eq!(to_hex(&hash_224(b"abc")), "4634270f707b6a54daae7530460842e20e37ed265ceee9a43e8924aa");
+        assert_eq!(to_hex(&hash_224(MSG_896)), "23fec5bb94d60b23308192640b0c453335d664734fe40e7268674af9");
+        assert_eq!(to_hex(&hash_256(b"abc")), "53048e2681941ef99b2e29b76b4c7dabe4c2d0c634fc6d46e0e2f13107e7af23");
+        assert_eq!(to_hex(&hash_256(MSG_896)), "3928e184fb8690f840da3988121d31be65cb9d3ef83ee6146feac861e19b563a");
+        assert_eq!(sha512_t(200).hash(b"abc").len(), 25);
+    }
+
+    #[test]
+    fn test_context() {
+        let mut ctx = Context256::new();
+        let mut ctx_t = sha512_t(256).context();
+        for chunk in MSG_896.chunks(9) {
+            ctx.update(chunk);
+            ctx_t.update(chunk);
+        }
+        let hash = ctx.finalize();
+        assert_eq!(hash, hash_256(MSG_896));
+        assert_eq!(ctx_t.finalize(), hash.to_vec());
+    }
+
+    #[test]
+    fn test_state() {
+        let msg = [MSG_896; 3].concat();
+        for t in [8, 200, 224, 256, 504] {
+            let mut ctx = sha512_t(t).context();
+            ctx.update(&msg[..150]);
+
+            let state = ctx.export_state();
+            assert_eq!(state[6] as usize, t / 8);
+            let mut resumed = Context::import_state(&state).unwrap();
+            assert_eq!(resumed.export_state(), state);
+
+            resumed.update(&msg[150..]);
+            assert_eq!(resumed.finalize(), sha512_t(t).hash(&msg), "t = {t}");
+        }
+
+        // Same function as the fixed SHA-512/256 context
+        let mut ctx = Context256::new();
+        ctx.update(&msg[..150]);
+        let mut resumed = Context::import_state(&ctx.export_state()).unwrap();
+        resumed.update(&msg[150..]);
+        assert_eq!(resumed.finalize(), hash_256(&msg).to_vec());
+        let state = sha512_t(256).context().export_state();
+        assert!(Context256::import_state(&state).is_ok());
+        assert!(Context224::import_state(&state).is_err());
+
+        // t = 384 and t = 512 aren't SHA-512/t, nor are 32 bits word states
+        assert_eq!(Context::import_state(&five_twelve::Context::new().export_state()).err(), Some(InvalidState));
+        assert!(Context::import_state(&crate::three_eight_four::Context::new().export_state()).is_err());
+        assert!(Context::import_state(&crate::two_five_six::Context::new().export_state()).is_err());
+        assert!(Context::import_state(b"").is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_384_is_rejected() {
+        sha512_t(384);
+    }
+
+    #[test]
+    fn test_verify() {
+        assert!(verify_224(b"abc", &hash_224(b"abc")));
+        assert!(verify_256(b"abc", &hash_256(b"abc")));
+        assert!(!verify_256(b"abd", &hash_256(b"abc")));
+        assert!(sha512_t(200).verify(b"abc", &sha512_t(200).hash(b"abc")));
+        assert!(!sha512_t(200).verify(b"abc", &sha512_t(208).hash(b"abc")));
+    }
+}
@@ -0,0 +1,14 @@
+// Initial hash values given by FIPS 180-4 for SHA-512/224 and SHA-512/256.
+// They are the output of the IV generation function, see sha512_t
+pub const H0_224: [u64; 8] = [
+    0x8c3d37c819544da2, 0x73e1996689dcd4d6, 0x1dfab7ae32ff9c82, 0x679dd514582f9fcf,
+    0x0f6d2b697bd44da8, 0x77e36f7304c48942, 0x3f9d85a86a1d36c8, 0x1112e6ad91d692a1,
+];
+
+pub const H0_256: [u64; 8] = [
+    0x22312194fc2bf72c, 0x9f555fa3c84c64c2, 0x2393b86b6f53b151, 0x963877195940eabd,
+    0x96283ee2a88effe3, 0xbe5e1e2553863992, 0x2b0199fc2c85b8aa, 0x0eb72ddc81c52ca2,
+];
+
+// XORed with the SHA-512 initial hash value to get the IV used by the generation function
+pub const IV_GEN_MASK: u64 = 0xa5a5a5a5a5a5a5a5;
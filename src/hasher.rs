@@ -1,4 +1,10 @@
-use num::{traits::{ToBytes, WrappingAdd}, PrimInt};
+use std::marker::PhantomData;
+use std::mem::size_of;
+
+use num::{traits::{ToBytes, WrappingAdd}, PrimInt, Zero};
+
+use crate::preprocessing;
+use crate::preprocessing::PaddedTail;
 
 // These are the index for the working vars since they are laid out in an array
 #[allow(non_upper_case_globals)]
@@ -18,100 +24,265 @@ const g_: usize = 6;
 #[allow(non_upper_case_globals)]
 const h_: usize = 7;
 
-// A generic struct with functions for SHA hashing.
-// Generic parameters were only tested for SHA-compliant values (<u32, 32, 64> and <u64; 64; 80>)
-pub struct Hasher<T, const HASH_SIZE_BYTES: usize, const MSG_SCHEDULE_SIZE: usize>
-where T: 
-    PrimInt +
-    WrappingAdd +
-    ToBytes
-{
-    k: [T;MSG_SCHEDULE_SIZE],
+// SHA-512 has the longest message schedule, SHA-256 only uses the first 64 words
+const MAX_ROUNDS: usize = 80;
+
+/// The two word sizes of SHA-2: u32 for the 512bits block variants and u64 for the 1024bits block ones.
+/// Everything that only depends on the block size (reading blocks and padding) lives here.
+pub trait Word: PrimInt + WrappingAdd + ToBytes + 'static {
+    // Size of a message block in bytes (16 words)
+    const BLOCK_BYTES: usize;
+
+    fn read_block(bytes: &[u8]) -> [Self; 16];
+
+    // Reads one big endian word from the first bytes of the slice
+    fn read_word(bytes: &[u8]) -> Self;
+
+    // num_bits_msg is taken modulo the size of the length field (64 bits for u32, 128 bits for u64)
+    fn pad_tail(tail: &[u8], num_bits_msg: u128) -> PaddedTail<Self>;
+}
+
+impl Word for u32 {
+    const BLOCK_BYTES: usize = 64;
+
+    fn read_block(bytes: &[u8]) -> [u32; 16] {
+        preprocessing::bytes_to_512_block(bytes)
+    }
+
+    fn read_word(bytes: &[u8]) -> u32 {
+        u32::from_be_bytes(bytes[..4].try_into().unwrap())
+    }
+
+    fn pad_tail(tail: &[u8], num_bits_msg: u128) -> PaddedTail<u32> {
+        preprocessing::pad_tail_512(tail, num_bits_msg as u64)
+    }
 }
 
-impl<T, const HASH_SIZE_BYTES: usize, const MSG_SCHEDULE_SIZE: usize> Hasher<T, HASH_SIZE_BYTES, MSG_SCHEDULE_SIZE>
-where T:
-    PrimInt +
-    WrappingAdd +
-    ToBytes
+impl Word for u64 {
+    const BLOCK_BYTES: usize = 128;
 
-{
-    pub fn new(k: [T; MSG_SCHEDULE_SIZE]) -> Self {
-        Self { k }
+    fn read_block(bytes: &[u8]) -> [u64; 16] {
+        preprocessing::bytes_to_1024_block(bytes)
+    }
+
+    fn read_word(bytes: &[u8]) -> u64 {
+        u64::from_be_bytes(bytes[..8].try_into().unwrap())
+    }
+
+    fn pad_tail(tail: &[u8], num_bits_msg: u128) -> PaddedTail<u64> {
+        preprocessing::pad_tail_1024(tail, num_bits_msg)
+    }
+}
+
+/// Everything that defines a SHA-2 variant. Adding a variant only takes a unit struct implementing this.
+pub trait Sha2Params: Clone {
+    type Word: Word;
+    // Always [u8; OUTPUT_BYTES]
+    type Output: AsRef<[u8]> + Copy + for<'a> TryFrom<&'a [u8]>;
+
+    // Number of rounds, also the size of the message schedule and of K
+    const ROUNDS: usize;
+    const K: &'static [Self::Word];
+    // Initial hash value
+    const H0: [Self::Word; 8];
+    // Size of the final hash in bytes, the hash value is truncated to this size
+    const OUTPUT_BYTES: usize;
+
+    fn sig_lc_0(x: Self::Word) -> Self::Word;
+    fn sig_lc_1(x: Self::Word) -> Self::Word;
+    fn sig_uc_0(x: Self::Word) -> Self::Word;
+    fn sig_uc_1(x: Self::Word) -> Self::Word;
+}
+
+// A generic struct with functions for SHA hashing, the variant is given by P
+pub struct Hasher<P: Sha2Params> {
+    params: PhantomData<P>,
+}
+
+// Derive would require P: Copy
+impl<P: Sha2Params> Clone for Hasher<P> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<P: Sha2Params> Copy for Hasher<P> {}
+
+impl<P: Sha2Params> Default for Hasher<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P: Sha2Params> Hasher<P> {
+    pub fn new() -> Self {
+        Self { params: PhantomData }
     }
 
     // These are some pretty standard bitwise functions that are used throughout hasing process
     #[inline(always)]
-    fn choice(a: T, b: T, c: T) -> T {
-        return (a & b) ^ ((!a) & c)
+    fn choice(a: P::Word, b: P::Word, c: P::Word) -> P::Word {
+        (a & b) ^ ((!a) & c)
     }
 
     #[inline(always)]
-    fn majority(a: T, b: T, c: T) -> T {
-        return (a & b) ^ (a & c) ^ (b & c);
+    fn majority(a: P::Word, b: P::Word, c: P::Word) -> P::Word {
+        (a & b) ^ (a & c) ^ (b & c)
     }
 
-    pub fn create_message_schedule(&self, block: [T;16], sig_0: fn (x: T) -> T, sig_1: fn (x: T) -> T) -> [T; MSG_SCHEDULE_SIZE] {
-        let mut w: [T; MSG_SCHEDULE_SIZE] = [T::zero(); MSG_SCHEDULE_SIZE];
+    // Word i of the message schedule from words i-2, i-7, i-15 and i-16.
+    // Luckily this is the same formula for both sha-256 and sha-512
+    #[inline(always)]
+    pub(crate) fn schedule_word(w_2: P::Word, w_7: P::Word, w_15: P::Word, w_16: P::Word) -> P::Word {
+        P::sig_lc_1(w_2).wrapping_add(&w_7).wrapping_add(&P::sig_lc_0(w_15)).wrapping_add(&w_16)
+    }
 
-        // Place the block data in the first 16 u32
-        for i in 0..16 {
-            w[i] = block[i];
-        }
+    // One round of the compression on the working variables, k and w are the round constant and schedule word
+    #[inline(always)]
+    pub(crate) fn round(h: &mut [P::Word; 8], k: P::Word, w: P::Word) {
+        let tmp_1 = h[h_].wrapping_add(&P::sig_uc_1(h[e_])).wrapping_add(&Self::choice(h[e_], h[f_], h[g_])).wrapping_add(&k).wrapping_add(&w);
+        let tmp_2 = P::sig_uc_0(h[a_]).wrapping_add(&Self::majority(h[a_], h[b_], h[c_]));
+
+        h[h_] = h[g_];
+        h[g_] = h[f_];
+        h[f_] = h[e_];
+        h[e_] = h[d_].wrapping_add(&tmp_1);
+        h[d_] = h[c_];
+        h[c_] = h[b_];
+        h[b_] = h[a_];
+        h[a_] = tmp_1.wrapping_add(&tmp_2);
+    }
+
+    // Only the first P::ROUNDS words are used
+    pub fn create_message_schedule(&self, block: [P::Word;16]) -> [P::Word; MAX_ROUNDS] {
+        let mut w: [P::Word; MAX_ROUNDS] = [Zero::zero(); MAX_ROUNDS];
+
+        // Place the block data in the first 16 words
+        w[..16].copy_from_slice(&block);
     
         // Expand data to the whole message schedule array
-        for i in 16..MSG_SCHEDULE_SIZE {
-            // Luckily this is the same formula for both sha-256 and sha-512
-            w[i] = sig_1(w[i-2]).wrapping_add(&w[i-7]).wrapping_add(&sig_0(w[i-15])).wrapping_add(&w[i-16]);
+        for i in 16..P::ROUNDS {
+            w[i] = Self::schedule_word(w[i-2], w[i-7], w[i-15], w[i-16]);
         }
     
         w
     }
 
     //h are the "a b c d e f g h" vars from the original implementation
-    pub fn compress_block(&self, h: &[T;8], w: [T; MSG_SCHEDULE_SIZE], sig_0: fn (x: T) -> T, sig_1: fn (x: T) -> T) -> [T;8] {
-        let mut h = h.clone(); // Actually initialize the working variables
-
-        for i in 0..MSG_SCHEDULE_SIZE {
-            let tmp_1 = h[h_].wrapping_add(&sig_1(h[e_])).wrapping_add(&Self::choice(h[e_], h[f_], h[g_])).wrapping_add(&self.k[i]).wrapping_add(&w[i]);
-            let tmp_2 = sig_0(h[a_]).wrapping_add(&Self::majority(h[a_], h[b_], h[c_]));
-
-            h[h_] = h[g_];
-            h[g_] = h[f_];
-            h[f_] = h[e_];
-            h[f_] = h[d_].wrapping_add(&tmp_1);
-            h[f_] = h[c_];
-            h[c_] = h[b_];
-            h[b_] = h[a_];
-            h[a_] = tmp_1.wrapping_add(&tmp_2);
+    pub fn compress_block(&self, h: &[P::Word;8], w: &[P::Word; MAX_ROUNDS]) -> [P::Word;8] {
+        let mut h = *h; // Actually initialize the working variables
+
+        for i in 0..P::ROUNDS {
+            Self::round(&mut h, P::K[i], w[i]);
         }
 
         h
     } 
 
-    pub fn hash(&self, h: [T;8], blocks: Vec<[T;16]>,     
-        sig_lc_0: fn (x: T) -> T,
-        sig_lc_1: fn (x: T) -> T,
-        sig_uc_0: fn (x: T) -> T,
-        sig_uc_1: fn (x: T) -> T) -> [T; 8] {
+    // Compresses a single block and adds the result to the current hash value in place
+    pub fn process_block(&self, h: &mut [P::Word;8], block: [P::Word;16]) {
+            let w = self.create_message_schedule(block);
+            let h_comp = self.compress_block(h, &w);
+    
+            // Add the compressed block to the current hash
+            // 🖕 loops
+            h[0] = h_comp[a_].wrapping_add(&h[0]);
+            h[1] = h_comp[b_].wrapping_add(&h[1]);
+            h[2] = h_comp[c_].wrapping_add(&h[2]);
+            h[3] = h_comp[d_].wrapping_add(&h[3]);
+            h[4] = h_comp[e_].wrapping_add(&h[4]);
+            h[5] = h_comp[f_].wrapping_add(&h[5]);
+            h[6] = h_comp[g_].wrapping_add(&h[6]);
+            h[7] = h_comp[h_].wrapping_add(&h[7]);
+    }
 
+    // Hashes message starting from the hash value h. When trailing_bits isn't 0 the last byte is partial,
+    // only its upper trailing_bits bits belong to the message. The length field is built from the byte count
+    // in a u128, a usize bit count would overflow on 32 bits targets for messages of 512MiB and more.
+    // Full blocks are read straight from the message, only the padded tail is built on the stack
+    pub fn hash(&self, h: [P::Word;8], message: &[u8], trailing_bits: u32) -> [P::Word; 8] {
+            debug_assert!(trailing_bits < 8 && (trailing_bits == 0 || !message.is_empty()));
             let mut h = h;
-            for block in blocks {
-                let w = self.create_message_schedule(block, sig_lc_0, sig_lc_1);
-                let h_comp = self.compress_block(&h, w, sig_uc_0, sig_uc_1);
-        
-                // Add the compressed block to the current hash
-                // 🖕 loops
-                h[0] = h_comp[a_].wrapping_add(&h[0]);
-                h[1] = h_comp[b_].wrapping_add(&h[1]);
-                h[2] = h_comp[c_].wrapping_add(&h[2]);
-                h[3] = h_comp[d_].wrapping_add(&h[3]);
-                h[4] = h_comp[e_].wrapping_add(&h[4]);
-                h[5] = h_comp[f_].wrapping_add(&h[5]);
-                h[6] = h_comp[g_].wrapping_add(&h[6]);
-                h[7] = h_comp[h_].wrapping_add(&h[7]);
+            let whole_len = message.len() - usize::from(trailing_bits > 0);
+            let full_len = whole_len - whole_len % P::Word::BLOCK_BYTES;
+            for chunk in message[..full_len].chunks_exact(P::Word::BLOCK_BYTES) {
+                self.process_block(&mut h, P::Word::read_block(chunk));
+            }
+
+            let num_bits_msg = whole_len as u128 * 8 + trailing_bits as u128;
+            let tail = P::Word::pad_tail(&message[full_len..], num_bits_msg);
+            for block in tail.blocks() {
+                self.process_block(&mut h, *block);
             }
         
             h
     }
-}
\ No newline at end of file
+
+    // Turns the hash value into bytes (big endian) truncated to the output size of the variant
+    pub fn output(&self, h: [P::Word;8]) -> P::Output {
+        let word_bytes = size_of::<P::Word>();
+        let mut bytes: [u8; 64] = [0; 64];
+        for i in 0..8 {
+            bytes[i*word_bytes..(i+1)*word_bytes].copy_from_slice(h[i].to_be_bytes().as_ref());
+        }
+
+        let output = match P::Output::try_from(&bytes[..P::OUTPUT_BYTES]) {
+            Ok(output) => output,
+            Err(_) => unreachable!("Sha2Params::Output must be OUTPUT_BYTES long"),
+        };
+
+        output
+    }
+
+    // One-shot hashing of message, see hash for trailing_bits
+    pub fn digest(&self, message: &[u8], trailing_bits: u32) -> P::Output {
+        self.output(self.hash(P::H0, message, trailing_bits))
+    }
+}
+
+// Splits the first num_bits bits of message into the bytes holding them and the number of bits used in the
+// last byte (0 when it is whole), the form Hasher::hash takes them in
+pub(crate) fn split_bits(message: &[u8], num_bits: u64) -> (&[u8], u32) {
+    let len = num_bits.div_ceil(8);
+    assert!(len <= message.len() as u64, "num_bits is {num_bits} but the message only has {} bits", message.len() as u64 * 8);
+    (&message[..len as usize], (num_bits % 8) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Context;
+    use crate::two_five_six::Sha256;
+    use crate::five_twelve::Sha512;
+
+    // The one-shot path pads the tail of the message itself, the streaming context pads its buffer
+    fn check_against_context<P: Sha2Params>(lengths: &[usize]) {
+        let msg: Vec<u8> = (0..300u32).map(|i| (i * 7 + 3) as u8).collect();
+
+        for &len in lengths {
+            for trailing_bits in [0, 1, 7] {
+                if len == 0 && trailing_bits > 0 {
+                    continue;
+                }
+
+                let mut ctx: Context<P> = Context::new();
+                let expected = if trailing_bits == 0 {
+                    ctx.update(&msg[..len]);
+                    ctx.finalize()
+                } else {
+                    ctx.update(&msg[..len - 1]);
+                    ctx.finalize_bits(msg[len - 1], trailing_bits)
+                };
+
+                let hash = Hasher::<P>::new().digest(&msg[..len], trailing_bits);
+                assert_eq!(hash.as_ref(), expected.as_ref(), "{len} bytes, {trailing_bits} trailing bits");
+            }
+        }
+    }
+
+    #[test]
+    fn test_hash_padding() {
+        check_against_context::<Sha256>(&[0, 1, 55, 56, 57, 63, 64, 65, 119, 120, 121, 128, 129, 300]);
+        check_against_context::<Sha512>(&[0, 1, 111, 112, 113, 127, 128, 129, 239, 240, 241, 256, 257, 300]);
+    }
+}
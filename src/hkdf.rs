@@ -0,0 +1,118 @@
+//! HKDF key derivation (RFC 5869) on top of [`crate::hmac`].
+//! ```
+//! use sha::hkdf;
+//! use sha::two_five_six::Context as Sha256;
+//!
+//! let okm = hkdf::derive::<Sha256>(b"salt", b"input keying material", b"context", 42).unwrap();
+//! assert_eq!(okm.len(), 42);
+//! ```
+
+use std::fmt;
+
+use crate::algorithm::Algorithm;
+use crate::hmac::Hmac;
+
+// The block counter is a single byte, which limits the output to 255 blocks
+const MAX_BLOCKS: usize = 255;
+
+/// Returned by [`expand`] when the requested output is longer than 255 * HashLen bytes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidLength;
+
+impl fmt::Display for InvalidLength {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "HKDF output length must be at most 255 times the hash length")
+    }
+}
+
+impl std::error::Error for InvalidLength {}
+
+/// Extracts a pseudorandom key from the input keying material.
+/// An empty salt is the same as a salt of HashLen zeros, as specified by the RFC.
+pub fn extract<A: Algorithm>(salt: &[u8], ikm: &[u8]) -> A::Output {
+    let mut mac: Hmac<A> = Hmac::new(salt);
+    mac.update(ikm);
+    mac.finalize()
+}
+
+/// Expands a pseudorandom key into `len` bytes of output keying material bound to `info`
+pub fn expand<A: Algorithm>(prk: &[u8], info: &[u8], len: usize) -> Result<Vec<u8>, InvalidLength> {
+    if len > MAX_BLOCKS * A::OUTPUT_BYTES {
+        return Err(InvalidLength);
+    }
+
+    // The key is the same for every block, so the keyed HMAC is built once and cloned
+    let keyed: Hmac<A> = Hmac::new(prk);
+    let mut okm: Vec<u8> = Vec::with_capacity(len);
+    let mut previous: Option<A::Output> = None;
+    let mut counter: u8 = 1;
+
+    while okm.len() < len {
+        let mut mac = keyed.clone();
+        if let Some(t) = previous {
+            mac.update(t.as_ref());
+        }
+        mac.update(info);
+        mac.update(&[counter]);
+
+        let t = mac.finalize();
+        let n = usize::min(A::OUTPUT_BYTES, len - okm.len());
+        okm.extend_from_slice(&t.as_ref()[..n]);
+
+        previous = Some(t);
+        counter = counter.wrapping_add(1);
+    }
+
+    Ok(okm)
+}
+
+/// Extract then expand in one call
+pub fn derive<A: Algorithm>(salt: &[u8], ikm: &[u8], info: &[u8], len: usize) -> Result<Vec<u8>, InvalidLength> {
+    let prk = extract::<A>(salt, ikm);
+    expand::<A>(prk.as_ref(), info, len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::to_hex;
+    use crate::two_five_six;
+    use crate::five_twelve;
+
+    // Test cases 1 to 3 from RFC 5869
+    #[test]
+    fn test_rfc_5869() {
+        let ikm = [0x0b; 22];
+        let salt: Vec<u8> = (0x00..=0x0c).collect();
+        let info: Vec<u8> = (0xf0..=0xf9).collect();
+        let prk = extract::<two_five_six::Context>(&salt, &ikm);
+        assert_eq!(to_hex(&prk), "077709362c2e32df0ddc3f0dc47bba6390b6c73bb50f9c3122ec844ad7c2b3e5");
+        assert_eq!(to_hex(&expand::<two_five_six::Context>(&prk, &info, 42).unwrap()), "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865");
+
+        let ikm: Vec<u8> = (0x00..=0x4f).collect();
+        let salt: Vec<u8> = (0x60..=0xaf).collect();
+        let info: Vec<u8> = (0xb0..=0xff).collect();
+        let prk = extract::<two_five_six::Context>(&salt, &ikm);
+        assert_eq!(to_hex(&prk), "06a6b88c5853361a06104c9ceb35b45cef760014904671014a193f40c15fc244");
+        assert_eq!(to_hex(&expand::<two_five_six::Context>(&prk, &info, 82).unwrap()), "b11e398dc80327a1c8e7f78c596a49344f012eda2d4efad8a050cc4c19afa97c59045a99cac7827271cb41c65e590e09da3275600c2f09b8367793a9aca3db71cc30c58179ec3e87c14c01d5c1f3434f1d87");
+
+        let prk = extract::<two_five_six::Context>(&[], &[0x0b; 22]);
+        assert_eq!(to_hex(&prk), "19ef24a32c717b167f33a91d6f648bdf96596776afdb6377ac434c1c293ccb04");
+        assert_eq!(to_hex(&derive::<two_five_six::Context>(&[], &[0x0b; 22], &[], 42).unwrap()), "8da4e775a563c18f715f802a063c5a31b8a11f5c5ee1879ec3454e5f3c738d2d9d201395faa4b61a96c8");
+    }
+
+    #[test]
+    fn test_sha512() {
+        let salt: Vec<u8> = (0x00..=0x0c).collect();
+        let info: Vec<u8> = (0xf0..=0xf9).collect();
+        assert_eq!(to_hex(&derive::<five_twelve::Context>(&salt, &[0x0b; 22], &info, 42).unwrap()), "832390086cda71fb47625bb5ceb168e4c8e26a1a16ed34d9fc7fe92c1481579338da362cb8d9f925d7cb");
+    }
+
+    #[test]
+    fn test_output_length() {
+        let prk = [0x01; 32];
+        assert_eq!(expand::<two_five_six::Context>(&prk, &[], 255 * 32).unwrap().len(), 255 * 32);
+        assert_eq!(expand::<two_five_six::Context>(&prk, &[], 255 * 32 + 1), Err(InvalidLength));
+        assert!(expand::<two_five_six::Context>(&prk, &[], 0).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,226 @@
+//! HMAC (RFC 2104) over any SHA-2 variant of the crate.
+//! ```
+//! use sha::hmac::{hmac, HmacSha256};
+//!
+//! let tag = hmac::<sha::two_five_six::Context>(b"key", b"Hello, World!");
+//!
+//! let mut mac = HmacSha256::new(b"key");
+//! mac.update(b"Hello, ");
+//! mac.update(b"World!");
+//! assert!(mac.verify(&tag));
+//! ```
+
+use crate::algorithm::Algorithm;
+use crate::constant_time;
+use crate::two_two_four;
+use crate::two_five_six;
+use crate::three_eight_four;
+use crate::five_twelve;
+use crate::five_twelve_t;
+
+const IPAD: u8 = 0x36;
+const OPAD: u8 = 0x5c;
+// RFC 2104 §5: truncated tags keep at least 80 bits
+const MIN_TRUNCATED_BYTES: usize = 10;
+
+pub type HmacSha224 = Hmac<two_two_four::Context>;
+pub type HmacSha256 = Hmac<two_five_six::Context>;
+pub type HmacSha384 = Hmac<three_eight_four::Context>;
+pub type HmacSha512 = Hmac<five_twelve::Context>;
+pub type HmacSha512_224 = Hmac<five_twelve_t::Context224>;
+pub type HmacSha512_256 = Hmac<five_twelve_t::Context256>;
+
+/// Incremental HMAC. Both the inner and outer contexts are keyed when created,
+/// so cloning a freshly created `Hmac` is a cheap way to reuse a key.
+#[derive(Clone)]
+pub struct Hmac<A: Algorithm> {
+    inner: A,
+    outer: A,
+}
+
+impl<A: Algorithm> Hmac<A> {
+    pub fn new(key: &[u8]) -> Self {
+        // Keys longer than a block are hashed first, shorter keys are padded with zeros
+        let mut key_block: Vec<u8> = vec![0; A::BLOCK_BYTES];
+        if key.len() > A::BLOCK_BYTES {
+            let hashed_key = A::digest(key);
+            key_block[..A::OUTPUT_BYTES].copy_from_slice(hashed_key.as_ref());
+        } else {
+            key_block[..key.len()].copy_from_slice(key);
+        }
+
+        let mut inner = A::default();
+        let mut outer = A::default();
+        inner.update(&key_block.iter().map(|b| b ^ IPAD).collect::<Vec<u8>>());
+        outer.update(&key_block.iter().map(|b| b ^ OPAD).collect::<Vec<u8>>());
+
+        Self { inner, outer }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.inner.update(data);
+    }
+
+    pub fn finalize(self) -> A::Output {
+        let mut outer = self.outer;
+        outer.update(self.inner.finalize().as_ref());
+        outer.finalize()
+    }
+
+    /// Checks the MAC against an expected tag in constant time. The tag must be the whole MAC,
+    /// see [`Hmac::verify_truncated`] for truncated tags.
+    pub fn verify(self, expected: &[u8]) -> bool {
+        constant_time::eq(self.finalize().as_ref(), expected)
+    }
+
+    /// Checks a truncated tag against the leftmost bytes of the MAC in constant time (RFC 2104 §5).
+    /// Tags shorter than min_len are rejected, and so are tags shorter than half the MAC or 10 bytes
+    /// whatever min_len is, since a short tag is easy to guess.
+    pub fn verify_truncated(self, tag: &[u8], min_len: usize) -> bool {
+        let min_len = min_len.max(A::OUTPUT_BYTES / 2).max(MIN_TRUNCATED_BYTES);
+        if tag.len() < min_len || tag.len() > A::OUTPUT_BYTES {
+            return false;
+        }
+
+        constant_time::eq(&self.finalize().as_ref()[..tag.len()], tag)
+    }
+}
+
+pub fn hmac<A: Algorithm>(key: &[u8], message: &[u8]) -> A::Output {
+    let mut mac: Hmac<A> = Hmac::new(key);
+    mac.update(message);
+    mac.finalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::to_hex;
+
+    // Test cases from RFC 4231 (key, data, HMAC-SHA-224, HMAC-SHA-256, HMAC-SHA-384, HMAC-SHA-512)
+    fn rfc_4231() -> Vec<(Vec<u8>, Vec<u8>, [&'static str; 4])> {
+        vec![
+            (vec![0x0b; 20], b"Hi There".to_vec(), [
+                "896fb1128abbdf196832107cd49df33f47b4b1169912ba4f53684b22",
+                "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7",
+                "afd03944d84895626b0825f4ab46907f15f9dadbe4101ec682aa034c7cebc59cfaea9ea9076ede7f4af152e8b2fa9cb6",
+                "87aa7cdea5ef619d4ff0b4241a1d6cb02379f4e2ce4ec2787ad0b30545e17cdedaa833b7d6b8a702038b274eaea3f4e4be9d914eeb61f1702e696c203a126854",
+            ]),
+            (b"Jefe".to_vec(), b"what do ya want for nothing?".to_vec(), [
+                "a30e01098bc6dbbf45690f3a7e9e6d0f8bbea2a39e6148008fd05e44",
+                "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+                "af45d2e376484031617f78d2b58a6b1b9c7ef464f5a01b47e42ec3736322445e8e2240ca5e69e2c78b3239ecfab21649",
+                "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea2505549758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737",
+            ]),
+            (vec![0xaa; 20], vec![0xdd; 50], [
+                "7fb3cb3588c6c1f6ffa9694d7d6ad2649365b0c1f65d69d1ec8333ea",
+                "773ea91e36800e46854db8ebd09181a72959098b3ef8c122d9635514ced565fe",
+                "88062608d3e6ad8a0aa2ace014c8a86f0aa635d947ac9febe83ef4e55966144b2a5ab39dc13814b94e3ab6e101a34f27",
+                "fa73b0089d56a284efb0f0756c890be9b1b5dbdd8ee81a3655f83e33b2279d39bf3e848279a722c806b485a47e67c807b946a337bee8942674278859e13292fb",
+            ]),
+            ((1..=25).collect(), vec![0xcd; 50], [
+                "6c11506874013cac6a2abc1bb382627cec6a90d86efc012de7afec5a",
+                "82558a389a443c0ea4cc819899f2083a85f0faa3e578f8077a2e3ff46729665b",
+                "3e8a69b7783c25851933ab6290af6ca77a9981480850009cc5577c6e1f573b4e6801dd23c4a7d679ccf8a386c674cffb",
+                "b0ba465637458c6990e5a8c5f61d4af7e576d97ff94b872de76f8050361ee3dba91ca5c11aa25eb4d679275cc5788063a5f19741120c4f2de2adebeb10a298dd",
+            ]),
+            (vec![0xaa; 131], b"Test Using Larger Than Block-Size Key - Hash Key First".to_vec(), [
+                "95e9a0db962095adaebe9b2d6f0dbce2d499f112f2d2b7273fa6870e",
+                "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54",
+                "4ece084485813e9088d2c63a041bc5b44f9ef1012a2b588f3cd11f05033ac4c60c2ef6ab4030fe8296248df163f44952",
+                "80b24263c7c1a3ebb71493c1dd7be8b49b46d1f41b4aeec1121b013783f8f3526b56d037e05f2598bd0fd2215d6a1e5295e64f73f63f0aec8b915a985d786598",
+            ]),
+            (vec![0xaa; 131], b"This is a test using a larger than block-size key and a larger than block-size data. The key needs to be hashed before being used by the HMAC algorithm.".to_vec(), [
+                "3a854166ac5d9f023f54d517d0b39dbd946770db9c2b95c9f6f565d1",
+                "9b09ffa71b942fcb27635fbcd5b0e944bfdc63644f0713938a7f51535c3a35e2",
+                "6617178e941f020d351e2f254e8fd32c602420feb0b8fb9adccebb82461e99c5a678cc31e799176d3860e6110c46523e",
+                "e37b6a775dc87dbaa4dfa9f96e5e3ffddebd71f8867289865df5a32d20cdc944b6022cac3c4982b10d5eeb55c3e4de15134676fb6de0446065c97440fa8c6a58",
+            ]),
+        ]
+    }
+
+    #[test]
+    fn test_rfc_4231() {
+        for (key, data, expected) in rfc_4231() {
+            assert_eq!(to_hex(&hmac::<two_two_four::Context>(&key, &data)), expected[0]);
+            assert_eq!(to_hex(&hmac::<two_five_six::Context>(&key, &data)), expected[1]);
+            assert_eq!(to_hex(&hmac::<three_eight_four::Context>(&key, &data)), expected[2]);
+            assert_eq!(to_hex(&hmac::<five_twelve::Context>(&key, &data)), expected[3]);
+        }
+
+        // Test case 5 truncates the output to 128 bits
+        let mac = HmacSha256::new(&[0x0c; 20]);
+        let mut truncated = hmac::<two_five_six::Context>(&[0x0c; 20], b"Test With Truncation").to_vec();
+        truncated.truncate(16);
+        assert_eq!(to_hex(&truncated), "a3b6167473100ee06e0c796c2955552b");
+        let mut mac_trunc = mac.clone();
+        mac_trunc.update(b"Test With Truncation");
+        assert!(mac_trunc.clone().verify_truncated(&truncated, 16));
+        assert!(!mac_trunc.verify(&truncated));
+    }
+
+    #[test]
+    fn test_sha512_t() {
+        let msg = b"The quick brown fox jumps over the lazy dog";
+        assert_eq!(to_hex(&hmac::<five_twelve_t::Context224>(b"key", msg)), "a1afb4f708cb63570639195121785ada3dc615989cc3c73f38e306a3");
+        assert_eq!(to_hex(&hmac::<five_twelve_t::Context256>(b"key", msg)), "7fb65e03577da9151a1016e9c2e514d4d48842857f13927f348588173dca6d89");
+    }
+
+    #[test]
+    fn test_incremental() {
+        let data = vec![0x42u8; 300];
+        let expected = hmac::<five_twelve::Context>(b"secret", &data);
+
+        let mut mac = HmacSha512::new(b"secret");
+        for chunk in data.chunks(17) {
+            mac.update(chunk);
+        }
+        assert_eq!(mac.finalize(), expected);
+    }
+
+    #[test]
+    fn test_verify() {
+        let tag = hmac::<two_five_six::Context>(b"key", b"message");
+
+        let mut mac = HmacSha256::new(b"key");
+        mac.update(b"message");
+        assert!(mac.clone().verify(&tag));
+
+        let mut wrong = tag;
+        wrong[31] ^= 1;
+        assert!(!mac.clone().verify(&wrong));
+        assert!(!mac.clone().verify(&[]));
+        assert!(!mac.clone().verify(&[0; 33]));
+
+        // Prefixes of the MAC are not the MAC
+        assert!(!mac.clone().verify(&tag[..1]));
+        assert!(!mac.clone().verify(&tag[..31]));
+    }
+
+    #[test]
+    fn test_verify_truncated() {
+        let tag = hmac::<two_five_six::Context>(b"key", b"message");
+        let mut mac = HmacSha256::new(b"key");
+        mac.update(b"message");
+
+        assert!(mac.clone().verify_truncated(&tag, 0));
+        assert!(mac.clone().verify_truncated(&tag[..16], 0));
+        assert!(mac.clone().verify_truncated(&tag[..20], 20));
+        // Shorter than min_len, than half the MAC or than 10 bytes
+        assert!(!mac.clone().verify_truncated(&tag[..16], 20));
+        assert!(!mac.clone().verify_truncated(&tag[..15], 0));
+        assert!(!mac.clone().verify_truncated(&tag[..1], 1));
+        assert!(!mac.clone().verify_truncated(&[], 0));
+
+        let mut wrong = tag;
+        wrong[0] ^= 1;
+        assert!(!mac.verify_truncated(&wrong[..16], 0));
+
+        // Half of a SHA-224 MAC is 14 bytes
+        let tag = hmac::<two_two_four::Context>(b"key", b"message");
+        let mut mac = HmacSha224::new(b"key");
+        mac.update(b"message");
+        assert!(mac.clone().verify_truncated(&tag[..14], 0));
+        assert!(!mac.verify_truncated(&tag[..13], 0));
+    }
+}
@@ -0,0 +1,139 @@
+//! Length extension attack on SHA-256 and SHA-512, for security testing and training.
+//!
+//! A SHA-2 digest is the whole chaining value after the padded message, so knowing only
+//! `H(secret || msg)` and the length of `secret || msg` is enough to compute
+//! `H(secret || msg || padding || extension)` without the secret. This is why `H(secret || msg)`
+//! must never be used as a MAC, use [`crate::hmac`] instead.
+//! ```
+//! use sha::length_extension;
+//! use sha::two_five_six::hash;
+//!
+//! let mac = hash(b"secret&user=guest");
+//!
+//! // Only the digest and the total length (secret included) are needed
+//! let forgery = length_extension::forge_256(&mac, 17, b"&admin=true");
+//! let forged_msg = [&b"secret&user=guest"[..], &forgery.suffix].concat();
+//! assert_eq!(hash(&forged_msg), forgery.digest);
+//! ```
+
+use std::mem::size_of;
+
+use num::traits::ToBytes;
+
+use crate::context::Context;
+use crate::hasher::{Sha2Params, Word};
+use crate::two_five_six::Sha256;
+use crate::five_twelve::Sha512;
+
+/// A forged message suffix and its digest
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Forgery<O> {
+    // Glue padding followed by the extension, to append after the original message
+    pub suffix: Vec<u8>,
+    // Digest of the original message followed by the suffix
+    pub digest: O,
+}
+
+/// Padding the hash appended to a message of message_len bytes, as computed by the SHA-256 padding
+pub fn padding_256(message_len: usize) -> Vec<u8> {
+    padding::<Sha256>(message_len)
+}
+
+/// Padding the hash appended to a message of message_len bytes, as computed by the SHA-512 padding
+pub fn padding_512(message_len: usize) -> Vec<u8> {
+    padding::<Sha512>(message_len)
+}
+
+/// Extends a message of `message_len` bytes (secret included) hashing to `digest` with `extension`
+pub fn forge_256(digest: &[u8; 32], message_len: usize, extension: &[u8]) -> Forgery<[u8; 32]> {
+    forge::<Sha256>(digest, message_len, extension)
+}
+
+/// Extends a message of `message_len` bytes (secret included) hashing to `digest` with `extension`
+pub fn forge_512(digest: &[u8; 64], message_len: usize, extension: &[u8]) -> Forgery<[u8; 64]> {
+    forge::<Sha512>(digest, message_len, extension)
+}
+
+// The padding doesn't depend on the content of the message, only on its length,
+// so it's the padded tail of a message of zeros minus the zeros
+fn padding<P: Sha2Params>(message_len: usize) -> Vec<u8> {
+    let block_bytes = P::Word::BLOCK_BYTES;
+    let tail_len = message_len % block_bytes;
+    let tail = vec![0u8; tail_len];
+
+    let mut padded: Vec<u8> = Vec::with_capacity(2 * block_bytes);
+    for block in P::Word::pad_tail(&tail, message_len as u128 * 8).blocks() {
+        for word in block {
+            padded.extend_from_slice(word.to_be_bytes().as_ref());
+        }
+    }
+
+    padded.split_off(tail_len)
+}
+
+// Only works for the variants whose digest is the whole chaining value
+fn forge<P: Sha2Params>(digest: &[u8], message_len: usize, extension: &[u8]) -> Forgery<P::Output> {
+    let word_bytes = size_of::<P::Word>();
+    assert_eq!(digest.len(), 8 * word_bytes, "the digest must hold the whole chaining value");
+
+    let mut h = P::H0;
+    for i in 0..8 {
+        h[i] = P::Word::read_word(&digest[i * word_bytes..]);
+    }
+
+    // The original message and its padding end on a block boundary, continue hashing from there
+    let glue = padding::<P>(message_len);
+    let mut ctx: Context<P> = Context::with_chaining_value(h, (message_len + glue.len()) as u128);
+    ctx.update(extension);
+
+    let mut suffix = glue;
+    suffix.extend_from_slice(extension);
+    Forgery { suffix, digest: ctx.finalize() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::two_five_six;
+    use crate::five_twelve;
+
+    const SECRET: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz0123456789abcdefghijklmnopqrstuvwxyz0123456789abcdefghijklmnopqrstuvwxyz0123456789abcdefghij";
+
+    #[test]
+    fn test_padding() {
+        // 0x80, zeros and the length in bits
+        assert_eq!(padding_256(3), [&[0x80][..], &[0; 52], &24u64.to_be_bytes()].concat());
+        assert_eq!(padding_512(3), [&[0x80][..], &[0; 108], &24u128.to_be_bytes()].concat());
+
+        for len in 0..300 {
+            assert_eq!((len + padding_256(len).len()) % 64, 0);
+            assert_eq!((len + padding_512(len).len()) % 128, 0);
+        }
+
+        // No room for the length after the 1 bit, takes an extra block
+        assert_eq!(padding_256(56).len(), 72);
+        assert_eq!(padding_512(112).len(), 144);
+    }
+
+    #[test]
+    fn test_forge() {
+        let msg = b"user=guest";
+        let extension = b"&admin=true&with=a-longer-extension-that-spans-more-than-one-sha-256-block-of-64-bytes";
+
+        // Secret lengths around the padding boundaries
+        for secret_len in [0, 1, 13, 45, 46, 53, 54, 64, 101, 102, 110, 118] {
+            let original = [&SECRET[..secret_len], &msg[..]].concat();
+
+            for ext in [&extension[..0], &extension[..11], &extension[..]] {
+                let forgery = forge_256(&two_five_six::hash(&original), original.len(), ext);
+                let forged = [&original[..], &forgery.suffix].concat();
+                assert!(forged.ends_with(ext));
+                assert_eq!(forgery.digest, two_five_six::hash(&forged));
+
+                let forgery = forge_512(&five_twelve::hash(&original), original.len(), ext);
+                let forged = [&original[..], &forgery.suffix].concat();
+                assert_eq!(forgery.digest, five_twelve::hash(&forged));
+            }
+        }
+    }
+}
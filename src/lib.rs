@@ -1,4 +1,4 @@
-//! Strict SHA-256 and SHA-512 implementation using this paper: https://csrc.nist.gov/files/pubs/fips/180-2/final/docs/fips180-2.pdf
+//! Strict SHA-224, SHA-256, SHA-384, SHA-512 and SHA-512/t implementation using this paper: https://csrc.nist.gov/files/pubs/fips/180-2/final/docs/fips180-2.pdf
 //! Usage:
 //! ```
 //! use sha::two_five_six::hash;
@@ -7,8 +7,37 @@
 //!     let hash_result: [u8; 32] = hash(b"Hello, World!");
 //! }
 //! ```
+//!
+//! With the `digest` feature, `two_five_six::Context` and `five_twelve::Context` implement the RustCrypto `digest` traits.
+//! The `tokio` feature adds async hashing of tokio streams in `async_io`.
+
+// Indexing loops mirror the notation of the paper, keep them as is
+#![allow(clippy::needless_range_loop)]
 
 mod preprocessing;
 mod hasher;
+mod context;
+#[cfg(feature = "digest")]
+mod digest_impl;
+pub mod algorithm;
+pub mod constant_time;
+pub use hasher::Sha2Params;
+pub use context::{Context, InvalidState};
+pub mod output;
+pub use output::{Digest, DecodeError, Sha224Digest, Sha256Digest, Sha384Digest, Sha512Digest};
+
+pub mod two_two_four;
 pub mod two_five_six;
-pub mod five_twelve;
\ No newline at end of file
+pub mod three_eight_four;
+pub mod five_twelve;
+pub mod five_twelve_t;
+pub mod hmac;
+pub mod hkdf;
+pub mod pbkdf2;
+pub mod stream;
+pub mod length_extension;
+pub mod checksum;
+pub mod tree;
+pub mod batch;
+#[cfg(feature = "tokio")]
+pub mod async_io;
@@ -0,0 +1,311 @@
+//! Digest values with hex and base64 formatting and parsing.
+//! ```
+//! use sha::Sha256Digest;
+//! use sha::two_five_six::hash;
+//!
+//! let digest = Sha256Digest::from(hash(b"Hello, World!"));
+//! assert_eq!(digest.to_string(), "dffd6021bb2bd5b0af676290809ec3a53191dd81c7f70a4b28688a362182986f");
+//! assert_eq!(digest, "DFFD6021BB2BD5B0AF676290809EC3A53191DD81C7F70A4B28688A362182986F".parse::<Sha256Digest>().unwrap());
+//! assert_eq!(Sha256Digest::from_base64(&digest.to_base64()), Ok(digest));
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::constant_time;
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const BASE64URL_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Returned when a string isn't the hex or base64 encoding of a digest of the expected size
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodeError;
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid digest encoding")
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// A digest of N bytes. Equality is constant time so it can be compared to a secret value.
+#[derive(Clone, Copy)]
+pub struct Digest<const N: usize>([u8; N]);
+
+pub type Sha224Digest = Digest<28>;
+pub type Sha256Digest = Digest<32>;
+pub type Sha384Digest = Digest<48>;
+pub type Sha512Digest = Digest<64>;
+
+impl<const N: usize> Digest<N> {
+    pub fn new(bytes: [u8; N]) -> Self {
+        Self(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; N] {
+        &self.0
+    }
+
+    pub fn into_bytes(self) -> [u8; N] {
+        self.0
+    }
+
+    pub fn from_hex(s: &str) -> Result<Self, DecodeError> {
+        Self::from_slice(&from_hex(s)?)
+    }
+
+    /// Standard base64 (RFC 4648 section 4), padded
+    pub fn to_base64(&self) -> String {
+        base64_encode(&self.0, BASE64_ALPHABET, true)
+    }
+
+    /// URL and filename safe base64 (RFC 4648 section 5), without padding
+    pub fn to_base64url(&self) -> String {
+        base64_encode(&self.0, BASE64URL_ALPHABET, false)
+    }
+
+    /// Standard base64, the padding is required
+    pub fn from_base64(s: &str) -> Result<Self, DecodeError> {
+        Self::from_slice(&base64_decode(s, BASE64_ALPHABET, true)?)
+    }
+
+    /// URL and filename safe base64, the padding is optional
+    pub fn from_base64url(s: &str) -> Result<Self, DecodeError> {
+        Self::from_slice(&base64_decode(s, BASE64URL_ALPHABET, false)?)
+    }
+
+    fn from_slice(bytes: &[u8]) -> Result<Self, DecodeError> {
+        bytes.try_into().map(Self).map_err(|_| DecodeError)
+    }
+}
+
+/// Lowercase hex of any bytes, for outputs that don't have a fixed size (truncated MACs, derived keys, SHA-512/t)
+pub fn to_hex(bytes: &[u8]) -> String {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    bytes.iter().flat_map(|byte| [DIGITS[(byte >> 4) as usize] as char, DIGITS[(byte & 0xf) as usize] as char]).collect()
+}
+
+/// Decodes hex of any length, upper or lower case. Nothing but hex digits is accepted, no sign, prefix or spaces.
+pub fn from_hex(s: &str) -> Result<Vec<u8>, DecodeError> {
+    let s = s.as_bytes();
+    if !s.len().is_multiple_of(2) {
+        return Err(DecodeError);
+    }
+
+    s.chunks_exact(2).map(|pair| Ok(hex_value(pair[0])? << 4 | hex_value(pair[1])?)).collect()
+}
+
+fn hex_value(c: u8) -> Result<u8, DecodeError> {
+    match c {
+        b'0'..=b'9' => Ok(c - b'0'),
+        b'a'..=b'f' => Ok(c - b'a' + 10),
+        b'A'..=b'F' => Ok(c - b'A' + 10),
+        _ => Err(DecodeError),
+    }
+}
+
+// Every 3 bytes become 4 characters of 6 bits, the last group is completed with zero bits
+fn base64_encode(bytes: &[u8], alphabet: &[u8; 64], pad: bool) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let mut group = [0u8; 3];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let n = (group[0] as u32) << 16 | (group[1] as u32) << 8 | group[2] as u32;
+
+        // 1 byte gives 2 characters, 2 bytes give 3 and 3 bytes give 4
+        for i in 0..chunk.len() + 1 {
+            out.push(alphabet[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+        if pad {
+            for _ in chunk.len()..3 {
+                out.push('=');
+            }
+        }
+    }
+
+    out
+}
+
+fn base64_decode(s: &str, alphabet: &[u8; 64], require_padding: bool) -> Result<Vec<u8>, DecodeError> {
+    let s = s.as_bytes();
+    let unpadded = s.strip_suffix(b"==").or_else(|| s.strip_suffix(b"=")).unwrap_or(s);
+    if (require_padding || unpadded.len() != s.len()) && !s.len().is_multiple_of(4) {
+        return Err(DecodeError);
+    }
+    // A single character in the last group doesn't even make one byte
+    if unpadded.len() % 4 == 1 {
+        return Err(DecodeError);
+    }
+
+    let mut out = Vec::with_capacity(unpadded.len() * 3 / 4);
+    for chunk in unpadded.chunks(4) {
+        let mut n: u32 = 0;
+        for (i, c) in chunk.iter().enumerate() {
+            let value = alphabet.iter().position(|a| a == c).ok_or(DecodeError)?;
+            n |= (value as u32) << (18 - 6 * i);
+        }
+
+        let num_bytes = chunk.len() - 1;
+        // The unused low bits must be zero so every digest has a single encoding
+        if n & (0xffffff >> (8 * num_bytes)) != 0 {
+            return Err(DecodeError);
+        }
+        for i in 0..num_bytes {
+            out.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+
+    Ok(out)
+}
+
+impl<const N: usize> From<[u8; N]> for Digest<N> {
+    fn from(bytes: [u8; N]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl<const N: usize> From<Digest<N>> for [u8; N] {
+    fn from(digest: Digest<N>) -> Self {
+        digest.0
+    }
+}
+
+impl<const N: usize> AsRef<[u8]> for Digest<N> {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl<const N: usize> PartialEq for Digest<N> {
+    fn eq(&self, other: &Self) -> bool {
+        constant_time::eq(&self.0, &other.0)
+    }
+}
+
+impl<const N: usize> Eq for Digest<N> {}
+
+// Compares to a raw hash, like the ones returned by the hash functions
+impl<const N: usize> PartialEq<[u8; N]> for Digest<N> {
+    fn eq(&self, other: &[u8; N]) -> bool {
+        constant_time::eq(&self.0, other)
+    }
+}
+
+impl<const N: usize> FromStr for Digest<N> {
+    type Err = DecodeError;
+
+    fn from_str(s: &str) -> Result<Self, DecodeError> {
+        Self::from_hex(s)
+    }
+}
+
+impl<const N: usize> fmt::LowerHex for Digest<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&to_hex(&self.0))
+    }
+}
+
+impl<const N: usize> fmt::UpperHex for Digest<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{:02X}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+// Lowercase hex, like sha256sum
+impl<const N: usize> fmt::Display for Digest<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(self, f)
+    }
+}
+
+impl<const N: usize> fmt::Debug for Digest<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Digest({:x})", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::two_five_six;
+    use crate::five_twelve;
+
+    #[test]
+    fn test_hex() {
+        let digest = Sha256Digest::from(two_five_six::hash(b"abc"));
+        let hex = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+        assert_eq!(digest.to_string(), hex);
+        assert_eq!(format!("{:x}", digest), hex);
+        assert_eq!(format!("{:X}", digest), hex.to_uppercase());
+        assert_eq!(format!("{:?}", digest), format!("Digest({hex})"));
+
+        assert_eq!(hex.parse::<Sha256Digest>(), Ok(digest));
+        assert_eq!(Sha256Digest::from_hex(&hex.to_uppercase()), Ok(digest));
+        assert_eq!(hex.parse::<Sha512Digest>(), Err(DecodeError));
+        assert_eq!(hex[1..].parse::<Sha256Digest>(), Err(DecodeError));
+        assert_eq!(hex.replace('a', "g").parse::<Sha256Digest>(), Err(DecodeError));
+
+        assert_eq!(to_hex(&two_five_six::hash(b"abc")), hex);
+        assert_eq!(to_hex(&[0x00, 0x0f, 0xa0, 0xff]), "000fa0ff");
+        assert_eq!(to_hex(&[]), "");
+
+        assert_eq!(from_hex("000fA0ff"), Ok(vec![0x00, 0x0f, 0xa0, 0xff]));
+        assert_eq!(from_hex(""), Ok(Vec::new()));
+        for bad in ["0", "+f", "-1", "0x", " f", "é0"] {
+            assert_eq!(from_hex(bad), Err(DecodeError), "{bad}");
+        }
+    }
+
+    // Examples from RFC 4648 section 10
+    #[test]
+    fn test_base64_vectors() {
+        let vectors = [("", ""), ("f", "Zg=="), ("fo", "Zm8="), ("foo", "Zm9v"), ("foob", "Zm9vYg=="), ("fooba", "Zm9vYmE="), ("foobar", "Zm9vYmFy")];
+        for (plain, encoded) in vectors {
+            assert_eq!(base64_encode(plain.as_bytes(), BASE64_ALPHABET, true), encoded);
+            assert_eq!(base64_decode(encoded, BASE64_ALPHABET, true), Ok(plain.as_bytes().to_vec()));
+            assert_eq!(base64_decode(encoded.trim_end_matches('='), BASE64URL_ALPHABET, false), Ok(plain.as_bytes().to_vec()));
+        }
+
+        // Missing padding, non zero unused bits, wrong alphabet
+        assert_eq!(base64_decode("Zg", BASE64_ALPHABET, true), Err(DecodeError));
+        assert_eq!(base64_decode("Zh==", BASE64_ALPHABET, true), Err(DecodeError));
+        assert_eq!(base64_decode("Z", BASE64URL_ALPHABET, false), Err(DecodeError));
+        assert_eq!(base64_decode("+w==", BASE64URL_ALPHABET, false), Err(DecodeError));
+    }
+
+    #[test]
+    fn test_base64() {
+        let digest = Sha512Digest::from(five_twelve::hash(b"Hello, World!"));
+
+        // 64 bytes don't fill the last group, both padding styles show up
+        let b64 = digest.to_base64();
+        assert!(b64.ends_with("=="));
+        assert_eq!(Sha512Digest::from_base64(&b64), Ok(digest));
+
+        let b64url = digest.to_base64url();
+        assert!(!b64url.contains(['+', '/', '=']));
+        assert_eq!(b64url, b64.trim_end_matches('=').replace('+', "-").replace('/', "_"));
+        assert_eq!(Sha512Digest::from_base64url(&b64url), Ok(digest));
+        assert_eq!(Sha256Digest::from_base64url(&b64url), Err(DecodeError));
+    }
+
+    #[test]
+    fn test_eq() {
+        let hash = two_five_six::hash(b"abc");
+        let digest = Sha256Digest::new(hash);
+        let mut other = hash;
+        other[31] ^= 1;
+
+        assert_eq!(digest, hash);
+        assert_ne!(digest, other);
+        assert_ne!(digest, Sha256Digest::from(other));
+        assert_eq!(digest.as_ref(), &hash);
+        assert_eq!(<[u8; 32]>::from(digest), hash);
+    }
+}
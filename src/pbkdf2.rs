@@ -0,0 +1,108 @@
+//! PBKDF2 password-based key derivation (RFC 8018) with HMAC over any SHA-2 variant.
+//! ```
+//! use sha::pbkdf2::pbkdf2;
+//! use sha::two_five_six::Context as Sha256;
+//!
+//! let key = pbkdf2::<Sha256>(b"password", b"salt", 10_000, 32).unwrap();
+//! assert_eq!(key.len(), 32);
+//! ```
+
+use std::fmt;
+
+use crate::algorithm::Algorithm;
+use crate::hmac::Hmac;
+
+// The block index is a 32 bits counter starting at 1
+const MAX_BLOCKS: u64 = u32::MAX as u64;
+
+/// Returned by [`pbkdf2`] for 0 iterations, or for an output longer than (2^32 - 1) * HashLen bytes
+/// ("derived key too long" in RFC 8018)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidParams;
+
+impl fmt::Display for InvalidParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PBKDF2 requires at least one iteration and at most (2^32 - 1) * HashLen output bytes")
+    }
+}
+
+impl std::error::Error for InvalidParams {}
+
+/// Derives `len` bytes from a password.
+///
+/// The HMAC inner and outer pad states are computed once from the password,
+/// each iteration only clones them, so the cost per iteration is two compressions
+/// of the previous block (plus padding) instead of rehashing the key.
+pub fn pbkdf2<A: Algorithm>(password: &[u8], salt: &[u8], iterations: u32, len: usize) -> Result<Vec<u8>, InvalidParams> {
+    if iterations == 0 || len.div_ceil(A::OUTPUT_BYTES) as u64 > MAX_BLOCKS {
+        return Err(InvalidParams);
+    }
+
+    let keyed: Hmac<A> = Hmac::new(password);
+    let mut derived: Vec<u8> = Vec::with_capacity(len);
+    let mut block_index: u32 = 1;
+
+    while derived.len() < len {
+        // U_1 = PRF(P, S || INT(i))
+        let mut mac = keyed.clone();
+        mac.update(salt);
+        mac.update(&block_index.to_be_bytes());
+        let mut u = mac.finalize();
+
+        // T_i = U_1 ^ U_2 ^ ... ^ U_c
+        let mut t: Vec<u8> = u.as_ref().to_vec();
+        for _ in 1..iterations {
+            let mut mac = keyed.clone();
+            mac.update(u.as_ref());
+            u = mac.finalize();
+
+            for (t_byte, u_byte) in t.iter_mut().zip(u.as_ref()) {
+                *t_byte ^= u_byte;
+            }
+        }
+
+        let n = usize::min(A::OUTPUT_BYTES, len - derived.len());
+        derived.extend_from_slice(&t[..n]);
+        // Only wraps after the last block, the length check keeps it below 2^32
+        block_index = block_index.wrapping_add(1);
+    }
+
+    Ok(derived)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::to_hex;
+    use crate::two_five_six;
+    use crate::five_twelve;
+
+    // RFC 6070 inputs, with the SHA-256 and SHA-512 outputs
+    #[test]
+    fn test_sha256() {
+        assert_eq!(to_hex(&pbkdf2::<two_five_six::Context>(b"password", b"salt", 1, 32).unwrap()), "120fb6cffcf8b32c43e7225256c4f837a86548c92ccc35480805987cb70be17b");
+        assert_eq!(to_hex(&pbkdf2::<two_five_six::Context>(b"password", b"salt", 2, 32).unwrap()), "ae4d0c95af6b46d32d0adff928f06dd02a303f8ef3c251dfd6e2d85a95474c43");
+        assert_eq!(to_hex(&pbkdf2::<two_five_six::Context>(b"password", b"salt", 4096, 32).unwrap()), "c5e478d59288c841aa530db6845c4c8d962893a001ce4e11a4963873aa98134a");
+        assert_eq!(to_hex(&pbkdf2::<two_five_six::Context>(b"passwordPASSWORDpassword", b"saltSALTsaltSALTsaltSALTsaltSALTsalt", 4096, 40).unwrap()), "348c89dbcbd32b2f32d814b8116e84cf2b17347ebc1800181c4e2a1fb8dd53e1c635518c7dac47e9");
+        assert_eq!(to_hex(&pbkdf2::<two_five_six::Context>(b"pass\0word", b"sa\0lt", 4096, 16).unwrap()), "89b69d0516f829893c696226650a8687");
+    }
+
+    #[test]
+    fn test_sha512() {
+        assert_eq!(to_hex(&pbkdf2::<five_twelve::Context>(b"password", b"salt", 1, 64).unwrap()), "867f70cf1ade02cff3752599a3a53dc4af34c7a669815ae5d513554e1c8cf252c02d470a285a0501bad999bfe943c08f050235d7d68b1da55e63f73b60a57fce");
+        assert_eq!(to_hex(&pbkdf2::<five_twelve::Context>(b"passwordPASSWORDpassword", b"saltSALTsaltSALTsaltSALTsaltSALTsalt", 4096, 64).unwrap()), "8c0511f4c6e597c6ac6315d8f0362e225f3c501495ba23b868c005174dc4ee71115b59f9e60cd9532fa33e0f75aefe30225c583a186cd82bd4daea9724a3d3b8");
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        assert_eq!(pbkdf2::<two_five_six::Context>(b"password", b"salt", 0, 32), Err(InvalidParams));
+
+        // Rejected before anything is allocated or hashed
+        #[cfg(target_pointer_width = "64")]
+        {
+            assert_eq!(pbkdf2::<two_five_six::Context>(b"password", b"salt", 1, (1 << 32) * 32), Err(InvalidParams));
+            assert_eq!(pbkdf2::<five_twelve::Context>(b"password", b"salt", 1, ((1 << 32) - 1) * 64 + 1), Err(InvalidParams));
+        }
+        assert_eq!(pbkdf2::<two_five_six::Context>(b"password", b"salt", 1, 0), Ok(Vec::new()));
+    }
+}
@@ -1,7 +1,3 @@
-// I'll need to find a way to make this less repetitive without scrificing clarity and speed of execution
-// But at the same time there will always be two variants for SHA2: 32bits and 64bits so making these functions generic isn't very relevant
-
-use std::vec::Vec;
 use std::convert::TryInto;
 use std::mem::size_of;
 
@@ -9,87 +5,185 @@ const BYTE_SIZE: usize = 8;
 const NUM_BYTES_512: usize = 512 / BYTE_SIZE;
 const NUM_BYTES_1024: usize = 1024 / BYTE_SIZE;
 
-fn bytes_to_512_blocks(bytes: &[u8]) -> Vec<[u32;16]> {
-    let num_blocks = bytes.len() / NUM_BYTES_512;
+// Reads a single 512bits block, bytes must be exactly 64 bytes long
+pub fn bytes_to_512_block(bytes: &[u8]) -> [u32;16] {
+    let mut block: [u32;16] = [0;16];
+    for j in 0..16 {
+        block[j] = u32::from_be_bytes(bytes[j*4..j*4+4].try_into().unwrap());
+    }
 
-    let mut blocks: Vec<[u32;16]> = Vec::with_capacity(num_blocks);
+    block
+}
 
-    for i in 0..num_blocks {
-        let mut bytes_for_block: [u32;16] = [0;16];
-        for j in 0..16 {
-            let start_idx = j*4 + NUM_BYTES_512 * i;
-            bytes_for_block[j] = u32::from_be_bytes(bytes[start_idx..start_idx+4].try_into().unwrap());
-        }
-        
-        blocks.push(bytes_for_block);
+// Reads a single 1024bits block, bytes must be exactly 128 bytes long
+pub fn bytes_to_1024_block(bytes: &[u8]) -> [u64;16] {
+    let mut block: [u64;16] = [0;16];
+    for j in 0..16 {
+        block[j] = u64::from_be_bytes(bytes[j*8..j*8+8].try_into().unwrap());
     }
 
-    blocks
+    block
 }
 
-fn bytes_to_1024_blocks(bytes: &[u8]) -> Vec<[u64;16]> {
-    let num_blocks = bytes.len() / NUM_BYTES_1024;
+// The padded end of a message: one or two blocks, kept on the stack
+pub struct PaddedTail<T> {
+    blocks: [[T;16]; 2],
+    num_blocks: usize,
+}
 
-    let mut blocks: Vec<[u64;16]> = Vec::with_capacity(num_blocks);
+impl<T> PaddedTail<T> {
+    pub fn blocks(&self) -> &[[T;16]] {
+        &self.blocks[..self.num_blocks]
+    }
+}
 
-    for i in 0..num_blocks {
-        let mut bytes_for_block: [u64;16] = [0;16];
-        for j in 0..16 {
-            let start_idx = j*8 + NUM_BYTES_1024 * i;
-            bytes_for_block[j] = u64::from_be_bytes(bytes[start_idx..start_idx+8].try_into().unwrap());
-        }
-        
-        blocks.push(bytes_for_block);
+// Appends the 1 bit right after the message and returns the number of bytes now used.
+// When the message doesn't end on a byte boundary, only the upper partial_bits bits of its last byte
+// belong to the message and the 1 goes in that same byte
+fn append_one_bit(padded: &mut [u8], tail_len: usize, partial_bits: u32) -> usize {
+    if partial_bits == 0 {
+        padded[tail_len] = 0b1000_0000; // <- just append a 1 after message
+        return tail_len + 1;
     }
 
-    blocks
+    let last = tail_len - 1;
+    padded[last] = (padded[last] & !(0xff >> partial_bits)) | (0b1000_0000 >> partial_bits);
+    tail_len
 }
 
-// Returns 512bits blocks from a message to hash (provided as a byte list)
-pub fn blockify_msg_512(msg: &[u8]) -> Vec<[u32;16]> {
-    const BLOCK_BITS: u32 = 512;
-    const REQUIRED_FREE_SPACE: u32 = 448;
-
-    let num_bits_msg = msg.len() * BYTE_SIZE;
-    let num_zeros_padding = (u32::wrapping_sub(REQUIRED_FREE_SPACE, num_bits_msg as u32 + 1)) % BLOCK_BITS;
-
-    let msg_size_as_bytes = (num_bits_msg as u64).to_be_bytes();
-    let num_0_bytes = (num_zeros_padding as usize - 7) / BYTE_SIZE; // -7 because 7 zeros are already in the byte that hold the 1
-    
-    let total_padded_size = msg.len() +
-    1 +             // The byte that holds the one padded after the msg 
-    num_0_bytes +   // all 0s
-    size_of::<u64>();             
-
-    let mut padded_msg: Vec<u8> = Vec::with_capacity(total_padded_size);
-    padded_msg.extend_from_slice(msg);
-    padded_msg.push(0b1000_0000); // <- just append a 1 after message
-    padded_msg.resize(padded_msg.len() + num_0_bytes, 0); // push the 0s
-    padded_msg.extend_from_slice(&msg_size_as_bytes);   // push the msg size
-
-    bytes_to_512_blocks(&padded_msg)
+// Pads the last incomplete chunk of a message into one or two 512bits blocks.
+// num_bits_msg is the length of the whole message, not just the tail.
+// The tail is less than 64 bytes, or up to 64 when its last byte is partial (num_bits_msg not a multiple of 8)
+pub fn pad_tail_512(tail: &[u8], num_bits_msg: u64) -> PaddedTail<u32> {
+    let mut padded: [u8; NUM_BYTES_512 * 2] = [0; NUM_BYTES_512 * 2];
+    padded[..tail.len()].copy_from_slice(tail);
+    let used = append_one_bit(&mut padded, tail.len(), (num_bits_msg % BYTE_SIZE as u64) as u32);
+
+    // The size needs 8 bytes at the end, if it doesn't fit we need a second block
+    let padded_len = if used + size_of::<u64>() <= NUM_BYTES_512 { NUM_BYTES_512 } else { NUM_BYTES_512 * 2 };
+    padded[padded_len - size_of::<u64>()..padded_len].copy_from_slice(&num_bits_msg.to_be_bytes());
+
+    PaddedTail {
+        blocks: [bytes_to_512_block(&padded[..NUM_BYTES_512]), bytes_to_512_block(&padded[NUM_BYTES_512..])],
+        num_blocks: padded_len / NUM_BYTES_512,
+    }
 }
 
-pub fn blockify_msg_1024(msg: &[u8]) -> Vec<[u64;16]> {    
-    const BLOCK_BITS: u64 = 1024;
-    const REQUIRED_FREE_SPACE: u64 = 896;
-
-    let num_bits_msg = msg.len() * BYTE_SIZE;
-    let num_zeros_padding = (u64::wrapping_sub(REQUIRED_FREE_SPACE, num_bits_msg as u64 + 1)) % BLOCK_BITS;
-
-    let msg_size_as_bytes = (num_bits_msg as u128).to_be_bytes();
-    let num_0_bytes = (num_zeros_padding as usize - 7) / BYTE_SIZE; // -7 because 7 zeros are already in the byte that hold the 1
-    
-    let total_padded_size = msg.len() +
-    1 +             // The byte that holds the one padded after the msg 
-    num_0_bytes +   // all 0s
-    size_of::<u128>();
-    
-    let mut padded_msg: Vec<u8> = Vec::with_capacity(total_padded_size);
-    padded_msg.extend_from_slice(msg);
-    padded_msg.push(0b1000_0000); // <- just append a 1 after message
-    padded_msg.resize(padded_msg.len() + num_0_bytes, 0); // push the 0s
-    padded_msg.extend_from_slice(&msg_size_as_bytes);   // push the msg size
-
-    bytes_to_1024_blocks(&padded_msg)
-}
\ No newline at end of file
+// Pads the last incomplete chunk of a message into one or two 1024bits blocks.
+// num_bits_msg is the length of the whole message, not just the tail.
+// The tail is less than 128 bytes, or up to 128 when its last byte is partial (num_bits_msg not a multiple of 8)
+pub fn pad_tail_1024(tail: &[u8], num_bits_msg: u128) -> PaddedTail<u64> {
+    let mut padded: [u8; NUM_BYTES_1024 * 2] = [0; NUM_BYTES_1024 * 2];
+    padded[..tail.len()].copy_from_slice(tail);
+    let used = append_one_bit(&mut padded, tail.len(), (num_bits_msg % BYTE_SIZE as u128) as u32);
+
+    // The size needs 16 bytes at the end, if it doesn't fit we need a second block
+    let padded_len = if used + size_of::<u128>() <= NUM_BYTES_1024 { NUM_BYTES_1024 } else { NUM_BYTES_1024 * 2 };
+    padded[padded_len - size_of::<u128>()..padded_len].copy_from_slice(&num_bits_msg.to_be_bytes());
+
+    PaddedTail {
+        blocks: [bytes_to_1024_block(&padded[..NUM_BYTES_1024]), bytes_to_1024_block(&padded[NUM_BYTES_1024..])],
+        num_blocks: padded_len / NUM_BYTES_1024,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Bytes of the padded tail, big endian
+    fn bytes_512(tail: &PaddedTail<u32>) -> Vec<u8> {
+        tail.blocks().iter().flatten().flat_map(|w| w.to_be_bytes()).collect()
+    }
+
+    fn bytes_1024(tail: &PaddedTail<u64>) -> Vec<u8> {
+        tail.blocks().iter().flatten().flat_map(|w| w.to_be_bytes()).collect()
+    }
+
+    #[test]
+    fn test_padding_layout() {
+        // The tail is what's left after the full blocks, the size field is the length of the whole message
+        for len in [0, 1, 55, 56, 63, 64, 65, 119, 120, 200] {
+            let tail_len = len % NUM_BYTES_512;
+            let bytes = bytes_512(&pad_tail_512(&vec![0xab; tail_len], len as u64 * 8));
+            assert_eq!(bytes.len(), (tail_len + 1 + 8).div_ceil(64) * 64, "{len}");
+
+            // 0x80 right after the message, then zeros, then the size in bits
+            assert!(bytes[..tail_len].iter().all(|b| *b == 0xab));
+            assert_eq!(bytes[tail_len], 0x80);
+            assert!(bytes[tail_len + 1..bytes.len() - 8].iter().all(|b| *b == 0));
+            assert_eq!(&bytes[bytes.len() - 8..], &(len as u64 * 8).to_be_bytes());
+        }
+
+        for len in [0, 111, 112, 127, 128, 239, 240, 300] {
+            let tail_len = len % NUM_BYTES_1024;
+            let bytes = bytes_1024(&pad_tail_1024(&vec![0xab; tail_len], len as u128 * 8));
+            assert_eq!(bytes.len(), (tail_len + 1 + 16).div_ceil(128) * 128, "{len}");
+
+            assert!(bytes[..tail_len].iter().all(|b| *b == 0xab));
+            assert_eq!(bytes[tail_len], 0x80);
+            assert!(bytes[tail_len + 1..bytes.len() - 16].iter().all(|b| *b == 0));
+            assert_eq!(&bytes[bytes.len() - 16..], &(len as u128 * 8).to_be_bytes());
+        }
+    }
+
+    #[test]
+    fn test_bit_padding() {
+        // 5 bits 01101 then the 1 bit, the leftover bits of the byte are cleared
+        let tail = pad_tail_512(&[0b0110_1111], 5);
+        assert_eq!(tail.blocks().len(), 1);
+        assert_eq!(tail.blocks()[0][0], 0b0110_1100 << 24);
+        assert_eq!(tail.blocks()[0][15], 5);
+
+        // 447 bits: the 1 bit ends the 56th byte and the size still fits in the block
+        assert_eq!(pad_tail_512(&[0xff; 56], 447).blocks().len(), 1);
+        assert_eq!(pad_tail_512(&[0xff; 57], 449).blocks().len(), 2);
+        // 63 bytes and 7 bits: the partial byte fills the first block
+        let tail = pad_tail_512(&[0xff; 64], 511);
+        assert_eq!(tail.blocks().len(), 2);
+        assert_eq!(tail.blocks()[0][15], 0xffff_ffff);
+        assert_eq!(tail.blocks()[1][15], 511);
+
+        // Partial byte after full blocks: only the size field knows about them
+        let bytes = bytes_512(&pad_tail_512(&[0xff], 64 * 8 + 3));
+        assert_eq!(bytes[0], 0b1111_0000);
+        assert_eq!(&bytes[56..], &(64u64 * 8 + 3).to_be_bytes());
+
+        assert_eq!(pad_tail_1024(&[0xff; 112], 895).blocks().len(), 1);
+        assert_eq!(pad_tail_1024(&[0xff; 113], 897).blocks().len(), 2);
+        let bytes = bytes_1024(&pad_tail_1024(&[0b1010_1010], 2));
+        assert_eq!(bytes[0], 0b1010_0000);
+        assert_eq!(&bytes[112..], &2u128.to_be_bytes());
+    }
+
+    // The message length is injected directly so no gigabytes are allocated
+    #[test]
+    fn test_large_lengths() {
+        // 512MiB is 2^32 bits, the first length that didn't fit in a u32
+        let tail = pad_tail_512(&[], 1 << 32);
+        let blocks = tail.blocks();
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0][14], 1);
+        assert_eq!(blocks[0][15], 0);
+
+        // Largest message allowed by SHA-256: 2^64 - 8 bits in bytes
+        let tail = pad_tail_512(&[0xab; 63], ((1 << 61) - 1) * 8);
+        let blocks = tail.blocks();
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[1][14], 0xffff_ffff);
+        assert_eq!(blocks[1][15], 0xffff_fff8);
+
+        // SHA-512 lengths go past 2^64 bits
+        let tail = pad_tail_1024(&[0xab; 5], 1 << 64);
+        let blocks = tail.blocks();
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0][0], 0xabab_abab_ab80_0000);
+        assert_eq!(blocks[0][14], 1);
+        assert_eq!(blocks[0][15], 0);
+
+        let tail = pad_tail_1024(&[], ((1 << 125) - 1) * 8);
+        let blocks = tail.blocks();
+        assert_eq!(blocks[0][14], 0xffff_ffff_ffff_ffff);
+        assert_eq!(blocks[0][15], 0xffff_ffff_ffff_fff8);
+    }
+}
@@ -0,0 +1,224 @@
+//! Readers and writers that hash the bytes going through them, to check a download or an
+//! unpacked artifact without reading it a second time.
+//! ```
+//! use std::io::{self, Read};
+//! use sha::stream::HashingReader;
+//! use sha::two_five_six::{hash, Context as Sha256};
+//!
+//! let expected = hash(b"Hello, World!");
+//! let mut reader: HashingReader<_, Sha256> = HashingReader::new(&b"Hello, World!"[..]).expect(expected);
+//!
+//! // Fails with InvalidData at EOF if the bytes don't match the expected digest
+//! let mut content = Vec::new();
+//! reader.read_to_end(&mut content).unwrap();
+//! assert_eq!(reader.digest(), expected);
+//! ```
+
+use std::io::{self, Read, Write};
+
+use crate::algorithm::Algorithm;
+use crate::constant_time;
+
+pub(crate) fn mismatch_error() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "SHA-2 digest mismatch")
+}
+
+pub(crate) fn matches<A: Algorithm>(ctx: &A, expected: &A::Output) -> bool {
+    constant_time::eq(ctx.clone().finalize().as_ref(), expected.as_ref())
+}
+
+/// Forwards reads to the inner reader and hashes every byte returned
+pub struct HashingReader<R: Read, A: Algorithm> {
+    inner: R,
+    ctx: A,
+    expected: Option<A::Output>,
+}
+
+impl<R: Read, A: Algorithm> HashingReader<R, A> {
+    pub fn new(inner: R) -> Self {
+        Self { inner, ctx: A::default(), expected: None }
+    }
+
+    /// Makes the read hitting EOF return an InvalidData error if the digest of everything read isn't `expected`
+    pub fn expect(mut self, expected: A::Output) -> Self {
+        self.expected = Some(expected);
+        self
+    }
+
+    /// Digest of the bytes read so far
+    pub fn digest(&self) -> A::Output {
+        self.ctx.clone().finalize()
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    // Reading through this reference bypasses the hash
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read, A: Algorithm> Read for HashingReader<R, A> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.ctx.update(&buf[..n]);
+
+        // An empty buffer also reads 0 bytes, that's not the end of the stream
+        if n == 0 && !buf.is_empty() {
+            if let Some(expected) = &self.expected {
+                if !matches(&self.ctx, expected) {
+                    return Err(mismatch_error());
+                }
+            }
+        }
+
+        Ok(n)
+    }
+}
+
+/// Forwards writes to the inner writer and hashes every byte it accepted
+pub struct HashingWriter<W: Write, A: Algorithm> {
+    inner: W,
+    ctx: A,
+    expected: Option<A::Output>,
+}
+
+impl<W: Write, A: Algorithm> HashingWriter<W, A> {
+    pub fn new(inner: W) -> Self {
+        Self { inner, ctx: A::default(), expected: None }
+    }
+
+    /// Makes [`HashingWriter::finish`] return an InvalidData error if the digest of everything written isn't `expected`
+    pub fn expect(mut self, expected: A::Output) -> Self {
+        self.expected = Some(expected);
+        self
+    }
+
+    /// Digest of the bytes written so far
+    pub fn digest(&self) -> A::Output {
+        self.ctx.clone().finalize()
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    // Writing through this reference bypasses the hash
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Flushes the inner writer and checks the expected digest if any, returns the writer and the digest
+    pub fn finish(mut self) -> io::Result<(W, A::Output)> {
+        self.inner.flush()?;
+
+        let digest = self.digest();
+        if let Some(expected) = &self.expected {
+            if !constant_time::eq(digest.as_ref(), expected.as_ref()) {
+                return Err(mismatch_error());
+            }
+        }
+
+        Ok((self.inner, digest))
+    }
+}
+
+impl<W: Write, A: Algorithm> Write for HashingWriter<W, A> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // Only what the inner writer took, the caller retries the rest
+        let n = self.inner.write(buf)?;
+        self.ctx.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::two_five_six::{self, Context as Sha256};
+    use crate::five_twelve::{self, Context as Sha512};
+
+    fn message() -> Vec<u8> {
+        (0..50_000u32).map(|i| (i * 13 % 256) as u8).collect()
+    }
+
+    // Accepts at most 7 bytes per write
+    struct ShortWriter(Vec<u8>);
+
+    impl Write for ShortWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let n = usize::min(buf.len(), 7);
+            self.0.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_reader() {
+        let msg = message();
+
+        let mut reader: HashingReader<_, Sha256> = HashingReader::new(&msg[..]);
+        let mut out = Vec::new();
+        reader.read_to_end(&mut out).unwrap();
+        assert_eq!(out, msg);
+        assert_eq!(reader.digest(), two_five_six::hash(&msg));
+
+        let mut reader: HashingReader<_, Sha512> = HashingReader::new(&msg[..]).expect(five_twelve::hash(&msg));
+        assert_eq!(io::copy(&mut reader, &mut io::sink()).unwrap(), msg.len() as u64);
+    }
+
+    #[test]
+    fn test_reader_mismatch() {
+        let msg = message();
+        let mut reader: HashingReader<_, Sha256> = HashingReader::new(&msg[..]).expect(two_five_six::hash(b"something else"));
+
+        // Everything is forwarded, the error only comes at EOF
+        let mut buf = vec![0u8; msg.len()];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, msg);
+        assert_eq!(reader.read(&mut [0u8; 0]).unwrap(), 0);
+        assert_eq!(reader.read(&mut [0u8; 16]).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_writer() {
+        let msg = message();
+
+        let mut writer: HashingWriter<_, Sha256> = HashingWriter::new(ShortWriter(Vec::new())).expect(two_five_six::hash(&msg));
+        writer.write_all(&msg).unwrap();
+        assert_eq!(writer.digest(), two_five_six::hash(&msg));
+        let (inner, digest) = writer.finish().unwrap();
+        assert_eq!(inner.0, msg);
+        assert_eq!(digest, two_five_six::hash(&msg));
+
+        let mut writer: HashingWriter<_, Sha512> = HashingWriter::new(Vec::new()).expect(five_twelve::hash(b""));
+        writer.write_all(&msg).unwrap();
+        assert_eq!(writer.finish().unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_tee() {
+        // Hashes both sides of a copy
+        let msg = message();
+        let mut reader: HashingReader<_, Sha256> = HashingReader::new(&msg[..]);
+        let mut writer: HashingWriter<_, Sha256> = HashingWriter::new(Vec::new());
+        io::copy(&mut reader, &mut writer).unwrap();
+
+        assert_eq!(reader.digest(), writer.digest());
+        assert_eq!(writer.finish().unwrap().0, msg);
+    }
+}
@@ -0,0 +1,112 @@
+mod constants;
+
+use crate::constant_time;
+use crate::hasher::{split_bits, Hasher, Sha2Params};
+use crate::five_twelve;
+use crate::five_twelve::{sig_lc_0, sig_lc_1, sig_uc_0, sig_uc_1};
+
+use constants::SQRT_23;
+use constants::SQRT_29;
+use constants::SQRT_31;
+use constants::SQRT_37;
+use constants::SQRT_41;
+use constants::SQRT_43;
+use constants::SQRT_47;
+use constants::SQRT_53;
+
+const FINAL_HASH_BYTES: usize = 48; // <-- 384 bits in bytes
+
+// Initial hash value
+const H0: [u64; 8] = [
+    SQRT_23, SQRT_29, SQRT_31,
+    SQRT_37, SQRT_41, SQRT_43,
+    SQRT_47, SQRT_53
+    ];
+
+// Same as SHA-512 with another initial hash value and a truncated output
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Sha384;
+
+impl Sha2Params for Sha384 {
+    type Word = u64;
+    type Output = [u8; FINAL_HASH_BYTES];
+
+    const ROUNDS: usize = 80;
+    const K: &'static [u64] = &five_twelve::constants::K;
+    const H0: [u64; 8] = H0;
+    const OUTPUT_BYTES: usize = FINAL_HASH_BYTES;
+
+    fn sig_lc_0(x: u64) -> u64 { sig_lc_0(x) }
+    fn sig_lc_1(x: u64) -> u64 { sig_lc_1(x) }
+    fn sig_uc_0(x: u64) -> u64 { sig_uc_0(x) }
+    fn sig_uc_1(x: u64) -> u64 { sig_uc_1(x) }
+}
+
+/// Streaming SHA-384 context, see [`crate::Context`]
+pub type Context = crate::context::Context<Sha384>;
+
+pub fn hash(message: &[u8]) -> [u8;FINAL_HASH_BYTES] {
+    Hasher::<Sha384>::new().digest(message, 0)
+}
+
+/// Hashes the first num_bits bits of message, see [`crate::five_twelve::hash_bits`]
+///
+/// # Panics
+///
+/// If num_bits is more than the bits of message
+pub fn hash_bits(message: &[u8], num_bits: u64) -> [u8;FINAL_HASH_BYTES] {
+    let (message, trailing_bits) = split_bits(message, num_bits);
+    Hasher::<Sha384>::new().digest(message, trailing_bits)
+}
+
+// Checks the hash of message against expected in constant time, for digests used as tokens
+pub fn verify(message: &[u8], expected: &[u8;FINAL_HASH_BYTES]) -> bool {
+    constant_time::eq(&hash(message), expected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash_to_str(hash: [u8; 48]) -> String {
+        crate::Digest::from(hash).to_string()
+    }
+
+    // Examples from https://csrc.nist.gov/projects/cryptographic-standards-and-guidelines/example-values
+    #[test]
+    fn test_hash() {
+        assert_eq!(hash_to_str(hash(b"abc")), "cb00753f45a35e8bb5a03d699ac65007272c32ab0eded1631a8b605a43ff5bed8086072ba1e7cc2358baeca134c825a7");
+        assert_eq!(hash_to_str(hash(b"abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu")), "09330c33f71147e83d192fc782cd1b4753111b173b3b05d22fa08086e3b0f712fcc7c71a557e2db966c3e9fa91746039");
+        assert_eq!(hash_to_str(hash(&[b'a'; 1_000_000])), "9d0e1809716474cb086e834e310a4a1ced149e9c00f248527972cec5704c2a5b07b8b3dc38ecc4ebae97ddd87f3d8985");
+    }
+
+    #[test]
+    fn test_context() {
+        let mut ctx = Context::new();
+        for chunk in b"abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu".chunks(7) {
+            ctx.update(chunk);
+        }
+        assert_eq!(hash_to_str(ctx.finalize()), "09330c33f71147e83d192fc782cd1b4753111b173b3b05d22fa08086e3b0f712fcc7c71a557e2db966c3e9fa91746039");
+    }
+
+    #[test]
+    fn test_hash_bits() {
+        // RFC 6234 test with 5 bits
+        assert_eq!(hash_to_str(hash_bits(&[0x10], 5)), "8d17be79e32b6718e07d8a603eb84ba0478f7fcfd1bb93995f7d1149e09143ac1ffcfc56820e469f3878d957a15a3fe4");
+
+        // Boundary vectors computed with an independent bit oriented implementation
+        let msg: Vec<u8> = (0..130u32).map(|i| ((i * 37 + 11) % 256) as u8).collect();
+        assert_eq!(hash_to_str(hash_bits(&msg, 895)), "8ce7363a4b091c607b1a5821a55a73223d234d39a5d1a7b98dee04aa87e2cd27d153109c9ce33342c4e82e0d5bbc9a67");
+    }
+
+    #[test]
+    fn test_verify() {
+        let mut expected = hash(b"Hello, World!");
+        assert!(verify(b"Hello, World!", &expected));
+        assert!(<Context as crate::algorithm::Algorithm>::verify(b"Hello, World!", &expected));
+        assert!(!verify(b"Hello, World?", &expected));
+
+        expected[0] ^= 1;
+        assert!(!verify(b"Hello, World!", &expected));
+    }
+}
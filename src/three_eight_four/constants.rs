@@ -0,0 +1,9 @@
+// These are the first 64 bits of the square roots of the 9th to 16th prime numbers (from 23 to 53)
+pub const SQRT_23: u64 = 0xcbbb9d5dc1059ed8;
+pub const SQRT_29: u64 = 0x629a292a367cd507;
+pub const SQRT_31: u64 = 0x9159015a3070dd17;
+pub const SQRT_37: u64 = 0x152fecd8f70e5939;
+pub const SQRT_41: u64 = 0x67332667ffc00b31;
+pub const SQRT_43: u64 = 0x8eb44a8768581511;
+pub const SQRT_47: u64 = 0xdb0c2e0d64f98fa7;
+pub const SQRT_53: u64 = 0x47b5481dbefa4fa4;
@@ -0,0 +1,381 @@
+//! Fingerprints of directory trees: every regular file is hashed, and the sorted list of paths and digests
+//! (the manifest) is hashed again into a single tree digest. Identical trees give identical digests wherever
+//! they are on disk.
+//!
+//! File lines use the sha*sum format so a manifest can be checked with `sha2sum --check` from inside the tree,
+//! symlinks that aren't followed are recorded as `SYMLINK (path) -> target` and checked against the link.
+//! Both names in those lines have `)` escaped as `\)`, on top of the escaping of file names.
+//! Empty directories don't change the digest.
+//! A file name or link target that isn't valid UTF-8 is an error.
+//! ```no_run
+//! use sha::tree::{hash_tree, Symlinks};
+//! use sha::two_five_six::Context as Sha256;
+//!
+//! let tree = hash_tree::<Sha256>("target/release".as_ref(), Symlinks::Record).unwrap();
+//! print!("{}", tree.manifest());
+//! println!("{}", sha::output::to_hex(&tree.digest()));
+//! ```
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::algorithm::{self, Algorithm};
+use crate::checksum::escape;
+use crate::output::to_hex;
+
+/// What to do with symbolic links found in the tree
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Symlinks {
+    // Hash what they point to as if it was in the tree
+    Follow,
+    // Record the link target without reading it
+    Record,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EntryKind<O> {
+    File(O),
+    Symlink(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry<O> {
+    // Relative to the root, components separated by '/'
+    pub path: String,
+    pub kind: EntryKind<O>,
+}
+
+pub struct Tree<A: Algorithm> {
+    // Sorted by path
+    pub entries: Vec<Entry<A::Output>>,
+}
+
+impl<A: Algorithm> Tree<A> {
+    /// One line per entry, in path order
+    pub fn manifest(&self) -> String {
+        let mut manifest = String::new();
+
+        for entry in &self.entries {
+            match &entry.kind {
+                EntryKind::File(hash) => {
+                    let (prefix, path) = escape(&entry.path);
+                    manifest += &format!("{prefix}{}  {path}\n", to_hex(hash.as_ref()));
+                }
+                EntryKind::Symlink(target) => {
+                    let prefix = if needs_symlink_escape(&entry.path) || needs_symlink_escape(target) { "\\" } else { "" };
+                    manifest += &format!("{prefix}SYMLINK ({}) -> {}\n", escape_symlink_name(&entry.path), escape_symlink_name(target));
+                }
+            }
+        }
+
+        manifest
+    }
+
+    /// Hash of the manifest
+    pub fn digest(&self) -> A::Output {
+        A::digest(self.manifest().as_bytes())
+    }
+}
+
+// The path and the target of SYMLINK lines are escaped like file names, and ')' as well so that the first
+// unescaped ')' always ends the path: a -> "b) -> c" and "a) -> b" -> c don't give the same line
+fn needs_symlink_escape(name: &str) -> bool {
+    name.contains(['\\', '\n', '\r', ')'])
+}
+
+fn escape_symlink_name(name: &str) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for c in name.chars() {
+        match c {
+            '\\' => escaped += "\\\\",
+            '\n' => escaped += "\\n",
+            '\r' => escaped += "\\r",
+            ')' => escaped += "\\)",
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape_symlink_name(name: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(name.len());
+    let mut chars = name.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next()? {
+            '\\' => unescaped.push('\\'),
+            'n' => unescaped.push('\n'),
+            'r' => unescaped.push('\r'),
+            ')' => unescaped.push(')'),
+            _ => return None,
+        }
+    }
+
+    Some(unescaped)
+}
+
+/// Parses a `SYMLINK (path) -> target` line of a manifest into the path and the target
+pub fn parse_symlink_line(line: &str) -> Option<(String, String)> {
+    let line = line.strip_suffix('\r').unwrap_or(line);
+    let (escaped, line) = match line.strip_prefix('\\') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let rest = line.strip_prefix("SYMLINK (")?;
+
+    // The path ends at the first ')' that isn't escaped
+    let mut chars = rest.char_indices();
+    let end = loop {
+        match chars.next()? {
+            (_, '\\') => {
+                chars.next()?;
+            }
+            (i, ')') => break i,
+            _ => {}
+        }
+    };
+    let (path, target) = (&rest[..end], rest[end..].strip_prefix(") -> ")?);
+
+    if escaped {
+        Some((unescape_symlink_name(path)?, unescape_symlink_name(target)?))
+    } else if needs_symlink_escape(path) || needs_symlink_escape(target) {
+        // Written with a backslash at the start of the line
+        None
+    } else {
+        Some((path.to_string(), target.to_string()))
+    }
+}
+
+/// Target of the symbolic link at path as recorded in a manifest, with '/' separators
+pub fn read_link_target(path: &Path) -> io::Result<String> {
+    let target = fs::read_link(path)?.into_os_string().into_string()
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "link target is not valid UTF-8"))?;
+    // A backslash is a separator only on Windows, elsewhere it's part of a name
+    Ok(if cfg!(windows) { target.replace('\\', "/") } else { target })
+}
+
+/// Walks the directory at root and hashes every regular file in it
+pub fn hash_tree<A: Algorithm>(root: &Path, symlinks: Symlinks) -> io::Result<Tree<A>> {
+    if !fs::metadata(root).map_err(|e| with_path(root, e))?.is_dir() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("{}: not a directory", root.display())));
+    }
+
+    let mut entries = Vec::new();
+    let mut ancestors = vec![fs::canonicalize(root)?];
+    walk::<A>(root, "", symlinks, &mut ancestors, &mut entries)?;
+
+    // Byte order of the paths, independent of the order the file system lists them
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(Tree { entries })
+}
+
+// ancestors holds the canonical paths of the directories being walked, to detect loops through followed symlinks
+fn walk<A: Algorithm>(
+    dir: &Path,
+    relative: &str,
+    symlinks: Symlinks,
+    ancestors: &mut Vec<PathBuf>,
+    entries: &mut Vec<Entry<A::Output>>,
+) -> io::Result<()> {
+    for dir_entry in fs::read_dir(dir).map_err(|e| with_path(dir, e))? {
+        let dir_entry = dir_entry.map_err(|e| with_path(dir, e))?;
+        let path = dir_entry.path();
+        // Names that aren't valid UTF-8 can't be written to the manifest without merging them with others
+        let name = dir_entry.file_name().into_string().map_err(|_| not_utf8(&path))?;
+        let entry_path = if relative.is_empty() { name } else { format!("{relative}/{name}") };
+
+        let mut file_type = dir_entry.file_type().map_err(|e| with_path(&path, e))?;
+        if file_type.is_symlink() {
+            match symlinks {
+                Symlinks::Record => {
+                    let target = read_link_target(&path).map_err(|e| with_path(&path, e))?;
+                    entries.push(Entry { path: entry_path, kind: EntryKind::Symlink(target) });
+                    continue;
+                }
+                Symlinks::Follow => {
+                    file_type = fs::metadata(&path).map_err(|e| with_path(&path, e))?.file_type();
+                }
+            }
+        }
+
+        if file_type.is_dir() {
+            let canonical = fs::canonicalize(&path).map_err(|e| with_path(&path, e))?;
+            if ancestors.contains(&canonical) {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{}: file system loop", path.display())));
+            }
+
+            ancestors.push(canonical);
+            walk::<A>(&path, &entry_path, symlinks, ancestors, entries)?;
+            ancestors.pop();
+        } else if file_type.is_file() {
+            let hash = algorithm::hash_file::<A>(&path).map_err(|e| with_path(&path, e))?;
+            entries.push(Entry { path: entry_path, kind: EntryKind::File(hash) });
+        }
+        // Sockets, fifos and devices aren't content
+    }
+
+    Ok(())
+}
+
+fn not_utf8(path: &Path) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("{}: file name is not valid UTF-8", path.display()))
+}
+
+fn with_path(path: &Path, e: io::Error) -> io::Error {
+    io::Error::new(e.kind(), format!("{}: {e}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::two_five_six::{self, Context as Sha256};
+    use crate::five_twelve::Context as Sha512;
+
+    // Fresh directory per test, removed by the test when done
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sha_tree_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn make_tree(root: &Path) {
+        fs::create_dir_all(root.join("sub/deeper")).unwrap();
+        fs::create_dir_all(root.join("empty")).unwrap();
+        fs::write(root.join("b.txt"), b"bbb").unwrap();
+        fs::write(root.join("a.txt"), b"abc").unwrap();
+        fs::write(root.join("sub/deeper/c.bin"), vec![3u8; 70_000]).unwrap();
+        fs::write(root.join("sub.txt"), b"").unwrap();
+    }
+
+    #[test]
+    fn test_manifest() {
+        let dir = temp_dir("manifest");
+        make_tree(&dir);
+
+        let tree = hash_tree::<Sha256>(&dir, Symlinks::Record).unwrap();
+        let expected = format!(
+            "{}  a.txt\n{}  b.txt\n{}  sub.txt\n{}  sub/deeper/c.bin\n",
+            to_hex(&two_five_six::hash(b"abc")),
+            to_hex(&two_five_six::hash(b"bbb")),
+            to_hex(&two_five_six::hash(b"")),
+            to_hex(&two_five_six::hash(&[3u8; 70_000]))
+        );
+        assert_eq!(tree.manifest(), expected);
+        assert_eq!(tree.digest(), two_five_six::hash(expected.as_bytes()));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let dir = temp_dir("deterministic");
+        make_tree(&dir.join("one"));
+        make_tree(&dir.join("two"));
+
+        let digest = hash_tree::<Sha512>(&dir.join("one"), Symlinks::Record).unwrap().digest();
+        assert_eq!(hash_tree::<Sha512>(&dir.join("two"), Symlinks::Record).unwrap().digest(), digest);
+
+        // Content and names are both part of the digest
+        fs::write(dir.join("two/a.txt"), b"abd").unwrap();
+        assert_ne!(hash_tree::<Sha512>(&dir.join("two"), Symlinks::Record).unwrap().digest(), digest);
+        fs::write(dir.join("two/a.txt"), b"abc").unwrap();
+        fs::rename(dir.join("two/b.txt"), dir.join("two/c.txt")).unwrap();
+        assert_ne!(hash_tree::<Sha512>(&dir.join("two"), Symlinks::Record).unwrap().digest(), digest);
+
+        assert_eq!(hash_tree::<Sha256>(&dir.join("one/a.txt"), Symlinks::Record).err().unwrap().kind(), io::ErrorKind::InvalidInput);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks() {
+        use std::os::unix::fs::symlink;
+
+        let dir = temp_dir("symlinks");
+        let root = dir.join("root");
+        make_tree(&root);
+        symlink("a.txt", root.join("link.txt")).unwrap();
+        symlink("sub", root.join("linked_dir")).unwrap();
+
+        let recorded = hash_tree::<Sha256>(&root, Symlinks::Record).unwrap();
+        let manifest = recorded.manifest();
+        assert!(manifest.contains("SYMLINK (link.txt) -> a.txt\n"));
+        assert!(manifest.contains("SYMLINK (linked_dir) -> sub\n"));
+        assert_eq!(parse_symlink_line("SYMLINK (link.txt) -> a.txt"), Some((String::from("link.txt"), String::from("a.txt"))));
+        assert!(!manifest.contains("linked_dir/"));
+
+        // Followed links look like copies
+        let followed = hash_tree::<Sha256>(&root, Symlinks::Follow).unwrap();
+        let manifest = followed.manifest();
+        assert!(manifest.contains(&format!("{}  link.txt\n", to_hex(&two_five_six::hash(b"abc")))));
+        assert!(manifest.contains("  linked_dir/deeper/c.bin\n"));
+
+        // A link to one of its parents would never end
+        symlink("..", root.join("sub/parent")).unwrap();
+        assert!(hash_tree::<Sha256>(&root, Symlinks::Record).is_ok());
+        assert_eq!(hash_tree::<Sha256>(&root, Symlinks::Follow).err().unwrap().kind(), io::ErrorKind::InvalidData);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+        use std::os::unix::fs::symlink;
+
+        let dir = temp_dir("non_utf8");
+        make_tree(&dir.join("names"));
+        make_tree(&dir.join("targets"));
+
+        // Both would be \u{fffd}.txt if replaced
+        fs::write(dir.join("names").join(OsStr::from_bytes(b"\xff.txt")), b"").unwrap();
+        fs::write(dir.join("names").join(OsStr::from_bytes(b"\xfe.txt")), b"").unwrap();
+        assert_eq!(hash_tree::<Sha256>(&dir.join("names"), Symlinks::Record).err().unwrap().kind(), io::ErrorKind::InvalidData);
+
+        symlink(OsStr::from_bytes(b"\xff"), dir.join("targets/link")).unwrap();
+        assert_eq!(hash_tree::<Sha256>(&dir.join("targets"), Symlinks::Record).err().unwrap().kind(), io::ErrorKind::InvalidData);
+
+        // A backslash is kept, the target isn't the same as a/b
+        fs::remove_file(dir.join("targets/link")).unwrap();
+        symlink("a\\b", dir.join("targets/link")).unwrap();
+        assert!(hash_tree::<Sha256>(&dir.join("targets"), Symlinks::Record).unwrap().manifest().contains("\\SYMLINK (link) -> a\\\\b\n"));
+        assert_eq!(parse_symlink_line("\\SYMLINK (link) -> a\\\\b"), Some((String::from("link"), String::from("a\\b"))));
+        assert_eq!(parse_symlink_line("\\SYMLINK (link) -> a\\b"), None);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_parentheses() {
+        use std::os::unix::fs::symlink;
+
+        // Both would be SYMLINK (a) -> b) -> c without escaping
+        let dir = temp_dir("symlink_parentheses");
+        fs::create_dir_all(dir.join("t1")).unwrap();
+        fs::create_dir_all(dir.join("t2")).unwrap();
+        symlink("b) -> c", dir.join("t1/a")).unwrap();
+        symlink("c", dir.join("t2/a) -> b")).unwrap();
+
+        let t1 = hash_tree::<Sha256>(&dir.join("t1"), Symlinks::Record).unwrap();
+        let t2 = hash_tree::<Sha256>(&dir.join("t2"), Symlinks::Record).unwrap();
+        assert_eq!(t1.manifest(), "\\SYMLINK (a) -> b\\) -> c\n");
+        assert_eq!(t2.manifest(), "\\SYMLINK (a\\) -> b) -> c\n");
+        assert_ne!(t1.digest(), t2.digest());
+
+        assert_eq!(parse_symlink_line(t1.manifest().trim_end()), Some((String::from("a"), String::from("b) -> c"))));
+        assert_eq!(parse_symlink_line(t2.manifest().trim_end()), Some((String::from("a) -> b"), String::from("c"))));
+        assert_eq!(parse_symlink_line("SYMLINK (a) -> b) -> c"), None);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,7 +1,12 @@
-mod constants;
+pub(crate) mod constants;
+mod lanes;
 
-use crate::preprocessing;
-use crate::hasher::Hasher;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::constant_time;
+use crate::hasher::{split_bits, Hasher, Sha2Params};
 
 // Shadows f32::constants::SQRT_X from std library
 use constants::SQRT_2;
@@ -14,68 +19,123 @@ use constants::SQRT_17;
 use constants::SQRT_19;
 
 #[inline(always)]
-fn sig_lc_0(x: u32) -> u32 {
+pub(crate) fn sig_lc_0(x: u32) -> u32 {
     x.rotate_right(7) ^ x.rotate_right(18) ^ x >> 3
 }
 
 #[inline(always)]
-fn sig_lc_1(x: u32) -> u32 {
+pub(crate) fn sig_lc_1(x: u32) -> u32 {
     x.rotate_right(17) ^ x.rotate_right(19) ^ x >> 10
 }
 
 #[inline(always)]
-fn sig_uc_0(x: u32) -> u32 {
+pub(crate) fn sig_uc_0(x: u32) -> u32 {
     x.rotate_right(2) ^ x.rotate_right(13) ^ x.rotate_right(22)
 }
 
 #[inline(always)]
-fn sig_uc_1(x: u32) -> u32 {
+pub(crate) fn sig_uc_1(x: u32) -> u32 {
     x.rotate_right(6) ^ x.rotate_right(11) ^ x.rotate_right(25)
 }
 
 const FINAL_HASH_BYTES: usize = 32; // <-- 256 bits in bytes
 
+// Initial hash value
+const H0: [u32; 8] = [
+    SQRT_2, SQRT_3, SQRT_5,
+    SQRT_7, SQRT_11, SQRT_13, 
+    SQRT_17, SQRT_19
+    ];
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Sha256;
+
+impl Sha2Params for Sha256 {
+    type Word = u32;
+    type Output = [u8; FINAL_HASH_BYTES];
+
+    const ROUNDS: usize = 64;
+    const K: &'static [u32] = &constants::K;
+    const H0: [u32; 8] = H0;
+    const OUTPUT_BYTES: usize = FINAL_HASH_BYTES;
+
+    fn sig_lc_0(x: u32) -> u32 { sig_lc_0(x) }
+    fn sig_lc_1(x: u32) -> u32 { sig_lc_1(x) }
+    fn sig_uc_0(x: u32) -> u32 { sig_uc_0(x) }
+    fn sig_uc_1(x: u32) -> u32 { sig_uc_1(x) }
+}
+
+/// Streaming SHA-256 context, see [`crate::Context`]
+pub type Context = crate::context::Context<Sha256>;
+
 pub fn hash(message: &[u8]) -> [u8;FINAL_HASH_BYTES] {
-        
-    let blocks = preprocessing::blockify_msg_512(message);
-
-    // Initial hash value
-    let h: [u32; 8] = [
-        SQRT_2, SQRT_3, SQRT_5,
-        SQRT_7, SQRT_11, SQRT_13, 
-        SQRT_17, SQRT_19
-        ];
-
-    let hasher: Hasher<u32, FINAL_HASH_BYTES, 64> = Hasher::new(constants::K);
-    
-    let h = hasher.hash(h, blocks, sig_lc_0, sig_lc_1, sig_uc_0, sig_uc_1);
-
-    // turn into byte array
-    let mut hash: [u8; FINAL_HASH_BYTES] = [0; FINAL_HASH_BYTES];
-    for i in 0..8 {
-        let bytes = u32::to_be_bytes(h[i]);
-        hash[i*4] = bytes[0];
-        hash[i*4+1] = bytes[1];
-        hash[i*4+2] = bytes[2];
-        hash[i*4+3] = bytes[3];
+    Hasher::<Sha256>::new().digest(message, 0)
+}
+
+/// Hashes the first num_bits bits of message, for messages that don't end on a byte boundary.
+/// Bits are read MSB first, the unused low bits of the last byte are ignored
+///
+/// # Panics
+///
+/// If num_bits is more than the bits of message
+pub fn hash_bits(message: &[u8], num_bits: u64) -> [u8;FINAL_HASH_BYTES] {
+    let (message, trailing_bits) = split_bits(message, num_bits);
+    Hasher::<Sha256>::new().digest(message, trailing_bits)
+}
+
+// Checks the hash of message against expected in constant time, for digests used as tokens
+pub fn verify(message: &[u8], expected: &[u8;FINAL_HASH_BYTES]) -> bool {
+    constant_time::eq(&hash(message), expected)
+}
+
+// Hashes every message on its own, LANES of them at a time interleaved word by word so the rounds run on
+// vector registers. Messages that don't fill a group of LANES go through hash. Best with messages of similar sizes
+pub fn hash_many(messages: &[&[u8]]) -> Vec<[u8;FINAL_HASH_BYTES]> {
+    let mut hashes = vec![[0; FINAL_HASH_BYTES]; messages.len()];
+    hash_many_into(messages, &mut hashes);
+    hashes
+}
+
+/// Same as [`hash_many`] without allocating, the hash of `messages[i]` goes to `hashes[i]`
+///
+/// # Panics
+///
+/// If the two slices don't have the same length
+pub fn hash_many_into(messages: &[&[u8]], hashes: &mut [[u8;FINAL_HASH_BYTES]]) {
+    assert_eq!(messages.len(), hashes.len(), "one hash per message");
+
+    let mut groups = messages.chunks_exact(lanes::LANES);
+    let mut outputs = hashes.chunks_exact_mut(lanes::LANES);
+    for (group, output) in (&mut groups).zip(&mut outputs) {
+        output.copy_from_slice(&lanes::hash_lanes(group.try_into().unwrap()));
+    }
+    for (message, output) in groups.remainder().iter().zip(outputs.into_remainder()) {
+        *output = hash(message);
     }
-    
-    hash
+}
+
+// Hashes everything the reader yields until EOF, through a fixed size buffer
+pub fn hash_reader<R: Read>(reader: R) -> io::Result<[u8;FINAL_HASH_BYTES]> {
+    let mut ctx = Context::new();
+    ctx.update_reader(reader)?;
+    Ok(ctx.finalize())
+}
+
+pub fn hash_file<P: AsRef<Path>>(path: P) -> io::Result<[u8;FINAL_HASH_BYTES]> {
+    hash_reader(File::open(path)?)
+}
+
+#[cfg(feature = "tokio")]
+pub async fn hash_async_reader<R: tokio::io::AsyncRead + Unpin>(reader: R) -> io::Result<[u8;FINAL_HASH_BYTES]> {
+    crate::async_io::hash_async_reader::<Context, R>(reader).await
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    // Utility function
     fn hash_to_str(hash: [u8; 32]) -> String {
-        let mut hash_str = String::from("");
-    
-        for i in 0..32 {
-            hash_str += &format!("{:02x?}", hash[i]);
-        }
-    
-        return hash_str;
+        crate::Digest::from(hash).to_string()
     }
 
     #[test]
@@ -85,4 +145,141 @@ mod tests {
         assert_eq!(hash_to_str(hash(b"Lorem ipsum dolor sit amet, consectetuer adipiscing elit. Aenean commodo ligula eget dolor. Aenean massa. Cum sociis natoque penatibus et magnis dis parturient montes, nascetur ridiculus mus. Donec quam felis, ultricies nec, pellentesque eu, pretium quis, sem. Nulla consequat massa quis enim. Donec pede justo, fringilla vel, aliquet nec, vulputate eget, arcu. In enim justo, rhoncus ut, imperdiet a, venenatis vitae, justo. Nullam dictum felis eu pede mollis pretium. Integer tincidunt. Cras dapibus. Vivamus elementum semper nisi. Aenean vulputate eleifend tellus. Aenean leo ligula, porttitor eu, consequat vitae, eleifend ac, enim. Aliquam lorem ante, dapibus in, viverra quis, feugiat a, tellus. Phasellus viverra nulla ut metus varius laoreet. Quisque rutrum. Aenean imperdiet. Etiam ultricies nisi vel augue. Curabitur ullamcorper ultricies nisi. Nam eget dui. Etiam rhoncus. Maecenas tempus, tellus eget condimentum rhoncus, sem quam semper libero, sit amet adipiscing sem neque sed ipsum. Nam quam nunc, blandit vel, luctus pulvinar, hendrerit id, lorem. Maecenas nec odio et ante tincidunt tempus. Donec vitae sapien ut libero venenatis faucibus. Nullam quis ante. Etiam sit amet orci eget eros faucibus tincidunt. Duis leo. Sed fringilla mauris sit amet nibh. Donec sodales sagittis magna. Sed consequat, leo eget bibendum sodales, augue velit cursus nunc,")), "4d0fcee44bd65ea0a0c983da992b053d6f5d94a25e91eae6a783f59fb5ef0cc1");
         assert_eq!(hash_to_str(hash(b"It is a long established fact that a reader will be distracted by the readable content of a page when looking at its layout. The point of using Lorem Ipsum is that it has a more-or-less normal distribution of letters, as opposed to using 'Content here, content here', making it look like readable English. Many desktop publishing packages and web page editors now use Lorem Ipsum as their default model text, and a search for 'lorem ipsum' will uncover many web sites still in their infancy. Various versions have evolved over the years, sometimes by accident, sometimes on purpose (injected humour and the like).")), "703190109e4e00d7d5a61fa3df9919da8dd57a3eb53c5b321b4841bad7212ed8");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_context() {
+        let msg: Vec<u8> = (0..1000u32).map(|i| (i * 7 % 256) as u8).collect();
+
+        // Every chunk size must give the same result as the one-shot function, including across block boundaries
+        for chunk_size in [1, 3, 55, 56, 63, 64, 65, 128, 1000] {
+            let mut ctx = Context::new();
+            for chunk in msg.chunks(chunk_size) {
+                ctx.update(chunk);
+            }
+            assert_eq!(ctx.finalize(), hash(&msg));
+        }
+
+        // Padding edge cases
+        for len in [0, 55, 56, 63, 64, 119, 120] {
+            let mut ctx = Context::new();
+            ctx.update(&msg[..len]);
+            assert_eq!(ctx.finalize(), hash(&msg[..len]));
+        }
+    }
+
+    #[test]
+    fn test_hash_bits() {
+        // RFC 6234 test with 5 bits
+        assert_eq!(hash_to_str(hash_bits(&[0x68], 5)), "d6d3e02a31a84a8caa9718ed6c2057be09db45e7823eb5079ce7a573a3760f95");
+
+        // Boundary vectors computed with an independent bit oriented implementation
+        let msg: Vec<u8> = (0..130u32).map(|i| ((i * 37 + 11) % 256) as u8).collect();
+        assert_eq!(hash_to_str(hash_bits(&msg, 447)), "debb15d7c8a946e6ab159390ebec6b1a166ebd214c0516fcb13506d66ec7adad");
+        assert_eq!(hash_to_str(hash_bits(&msg, 449)), "7f0928a907ef6c4f47abe8d6970ecb2efaa5f75cc03147a04e9eb0c3c71ec579");
+        assert_eq!(hash_to_str(hash_bits(&msg, 511)), "41c9cd8823c1fe7dda3fb8ea9430552b77a7161cd8bb9d8a6d5890af3edeac41");
+
+        // Byte aligned lengths are plain hashes
+        assert_eq!(hash_bits(&msg, 800), hash(&msg[..100]));
+        assert_eq!(hash_bits(&msg, 0), hash(b""));
+
+        // The streaming path gives the same result
+        for num_bits in [5, 447, 449, 511] {
+            let mut ctx = Context::new();
+            ctx.update(&msg[..num_bits / 8]);
+            assert_eq!(ctx.finalize_bits(msg[num_bits / 8], (num_bits % 8) as u32), hash_bits(&msg, num_bits as u64));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "num_bits is 1041 but the message only has 1040 bits")]
+    fn test_hash_bits_too_long() {
+        hash_bits(&[0; 130], 130 * 8 + 1);
+    }
+
+    // Yields a few bytes at a time and gets interrupted between reads
+    struct SlowReader<'a> {
+        data: &'a [u8],
+        interrupt: bool,
+    }
+
+    impl Read for SlowReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.interrupt = !self.interrupt;
+            if self.interrupt {
+                return Err(io::Error::from(io::ErrorKind::Interrupted));
+            }
+            let n = usize::min(usize::min(buf.len(), 13), self.data.len());
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_hash_reader() {
+        // Larger than the read buffer so it's refilled a few times
+        let msg: Vec<u8> = (0..100_000u32).map(|i| (i * 31 % 251) as u8).collect();
+
+        assert_eq!(hash_reader(io::Cursor::new(&msg)).unwrap(), hash(&msg));
+        assert_eq!(hash_reader(io::empty()).unwrap(), hash(b""));
+        assert_eq!(hash_reader(SlowReader { data: &msg[..1000], interrupt: false }).unwrap(), hash(&msg[..1000]));
+
+        let mut ctx = Context::new();
+        assert_eq!(io::copy(&mut &msg[..], &mut ctx).unwrap(), msg.len() as u64);
+        assert_eq!(ctx.finalize(), hash(&msg));
+    }
+
+    #[test]
+    fn test_hash_file() {
+        let msg: Vec<u8> = (0..70_000u32).map(|i| (i * 17 % 256) as u8).collect();
+        let path = std::env::temp_dir().join(format!("sha_test_hash_file_two_five_six_{}", std::process::id()));
+        std::fs::write(&path, &msg).unwrap();
+        let result = hash_file(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(result.unwrap(), hash(&msg));
+        assert_eq!(hash_file(path).unwrap_err().kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_verify() {
+        let mut expected = hash(b"Hello, World!");
+        assert!(verify(b"Hello, World!", &expected));
+        assert!(<Context as crate::algorithm::Algorithm>::verify(b"Hello, World!", &expected));
+        assert!(!verify(b"Hello, World?", &expected));
+
+        expected[0] ^= 1;
+        assert!(!verify(b"Hello, World!", &expected));
+    }
+
+    #[test]
+    fn test_hash_many() {
+        // Lengths around the padding boundaries, mixed so lanes finish at different blocks
+        let lengths = [0, 1, 55, 56, 63, 64, 65, 119, 120, 128, 1000, 4096, 3, 200, 64, 0, 9999];
+        let messages: Vec<Vec<u8>> = lengths.iter().enumerate().map(|(i, &len)| (0..len).map(|j| (i * 31 + j) as u8).collect()).collect();
+
+        for count in [0, 1, 7, 8, 9, 16, 17] {
+            let slices: Vec<&[u8]> = messages[..count].iter().map(Vec::as_slice).collect();
+            let expected: Vec<[u8; 32]> = slices.iter().map(|message| hash(message)).collect();
+            assert_eq!(hash_many(&slices), expected, "{count} messages");
+
+            let mut hashes = vec![[0; 32]; count];
+            hash_many_into(&slices, &mut hashes);
+            assert_eq!(hashes, expected, "{count} messages");
+        }
+    }
+
+    #[test]
+    fn test_hash_lanes() {
+        // Known vectors in every lane position
+        let messages: [&[u8]; lanes::LANES] = [b"", b"abc", b"", b"abc", b"abc", b"", b"abc", b""];
+        for (message, hash) in messages.iter().zip(lanes::hash_lanes(&messages)) {
+            let expected = if message.is_empty() {
+                "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+            } else {
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+            };
+            assert_eq!(hash_to_str(hash), expected);
+        }
+    }
+}
@@ -0,0 +1,112 @@
+mod constants;
+
+use crate::constant_time;
+use crate::hasher::{split_bits, Hasher, Sha2Params};
+use crate::two_five_six;
+use crate::two_five_six::{sig_lc_0, sig_lc_1, sig_uc_0, sig_uc_1};
+
+use constants::SQRT_23;
+use constants::SQRT_29;
+use constants::SQRT_31;
+use constants::SQRT_37;
+use constants::SQRT_41;
+use constants::SQRT_43;
+use constants::SQRT_47;
+use constants::SQRT_53;
+
+const FINAL_HASH_BYTES: usize = 28; // <-- 224 bits in bytes
+
+// Initial hash value
+const H0: [u32; 8] = [
+    SQRT_23, SQRT_29, SQRT_31,
+    SQRT_37, SQRT_41, SQRT_43,
+    SQRT_47, SQRT_53
+    ];
+
+// Same as SHA-256 with another initial hash value and a truncated output
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Sha224;
+
+impl Sha2Params for Sha224 {
+    type Word = u32;
+    type Output = [u8; FINAL_HASH_BYTES];
+
+    const ROUNDS: usize = 64;
+    const K: &'static [u32] = &two_five_six::constants::K;
+    const H0: [u32; 8] = H0;
+    const OUTPUT_BYTES: usize = FINAL_HASH_BYTES;
+
+    fn sig_lc_0(x: u32) -> u32 { sig_lc_0(x) }
+    fn sig_lc_1(x: u32) -> u32 { sig_lc_1(x) }
+    fn sig_uc_0(x: u32) -> u32 { sig_uc_0(x) }
+    fn sig_uc_1(x: u32) -> u32 { sig_uc_1(x) }
+}
+
+/// Streaming SHA-224 context, see [`crate::Context`]
+pub type Context = crate::context::Context<Sha224>;
+
+pub fn hash(message: &[u8]) -> [u8;FINAL_HASH_BYTES] {
+    Hasher::<Sha224>::new().digest(message, 0)
+}
+
+/// Hashes the first num_bits bits of message, see [`crate::two_five_six::hash_bits`]
+///
+/// # Panics
+///
+/// If num_bits is more than the bits of message
+pub fn hash_bits(message: &[u8], num_bits: u64) -> [u8;FINAL_HASH_BYTES] {
+    let (message, trailing_bits) = split_bits(message, num_bits);
+    Hasher::<Sha224>::new().digest(message, trailing_bits)
+}
+
+// Checks the hash of message against expected in constant time, for digests used as tokens
+pub fn verify(message: &[u8], expected: &[u8;FINAL_HASH_BYTES]) -> bool {
+    constant_time::eq(&hash(message), expected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash_to_str(hash: [u8; 28]) -> String {
+        crate::Digest::from(hash).to_string()
+    }
+
+    // Examples from https://csrc.nist.gov/projects/cryptographic-standards-and-guidelines/example-values
+    #[test]
+    fn test_hash() {
+        assert_eq!(hash_to_str(hash(b"abc")), "23097d223405d8228642a477bda255b32aadbce4bda0b3f7e36c9da7");
+        assert_eq!(hash_to_str(hash(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq")), "75388b16512776cc5dba5da1fd890150b0c6455cb4f58b1952522525");
+        assert_eq!(hash_to_str(hash(&[b'a'; 1_000_000])), "20794655980c91d8bbb4c1ea97618a4bf03f42581948b2ee4ee7ad67");
+    }
+
+    #[test]
+    fn test_context() {
+        let mut ctx = Context::new();
+        for chunk in b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq".chunks(5) {
+            ctx.update(chunk);
+        }
+        assert_eq!(hash_to_str(ctx.finalize()), "75388b16512776cc5dba5da1fd890150b0c6455cb4f58b1952522525");
+    }
+
+    #[test]
+    fn test_hash_bits() {
+        // RFC 6234 test with 5 bits
+        assert_eq!(hash_to_str(hash_bits(&[0x68], 5)), "e3b048552c3c387bcab37f6eb06bb79b96a4aee5ff27f51531a9551c");
+
+        // Boundary vectors computed with an independent bit oriented implementation
+        let msg: Vec<u8> = (0..130u32).map(|i| ((i * 37 + 11) % 256) as u8).collect();
+        assert_eq!(hash_to_str(hash_bits(&msg, 447)), "db41803b48dacb2deb05c66a7417da3f1e893e4ab6d61118f4cd863e");
+    }
+
+    #[test]
+    fn test_verify() {
+        let mut expected = hash(b"Hello, World!");
+        assert!(verify(b"Hello, World!", &expected));
+        assert!(<Context as crate::algorithm::Algorithm>::verify(b"Hello, World!", &expected));
+        assert!(!verify(b"Hello, World?", &expected));
+
+        expected[0] ^= 1;
+        assert!(!verify(b"Hello, World!", &expected));
+    }
+}
@@ -0,0 +1,9 @@
+// These are the second 32 bits of the square roots of the 9th to 16th prime numbers (from 23 to 53)
+pub const SQRT_23: u32 = 0xc1059ed8;
+pub const SQRT_29: u32 = 0x367cd507;
+pub const SQRT_31: u32 = 0x3070dd17;
+pub const SQRT_37: u32 = 0xf70e5939;
+pub const SQRT_41: u32 = 0xffc00b31;
+pub const SQRT_43: u32 = 0x68581511;
+pub const SQRT_47: u32 = 0x64f98fa7;
+pub const SQRT_53: u32 = 0xbefa4fa4;
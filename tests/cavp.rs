@@ -0,0 +1,245 @@
+// NIST CAVP conformance tests, using the byte oriented SHAVS vectors from
+// https://csrc.nist.gov/projects/cryptographic-algorithm-validation-program/secure-hashing
+// The .rsp files are kept as published in tests/data/shabytetestvectors.
+// check_msg_file also handles the bit oriented files (Len not a multiple of 8).
+// The bit oriented ones go in tests/data/shabittestvectors, as published in shabittestvectors.zip.
+// Those, and the SHA512_224 and SHA512_256 files of shabytetestvectors.zip, aren't in the repository yet:
+// their tests are ignored until the files are added
+
+use std::fs;
+use std::path::Path;
+
+use sha::algorithm::Algorithm;
+use sha::two_two_four;
+use sha::two_five_six;
+use sha::three_eight_four;
+use sha::five_twelve;
+use sha::five_twelve_t;
+
+fn hex_to_bytes(hex: &str) -> Vec<u8> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i+2], 16).unwrap())
+        .collect()
+}
+
+// Every "Key = Value" line of a .rsp file, section headers and comments are skipped.
+// file_name is relative to tests/data
+fn parse_rsp(file_name: &str) -> Vec<(String, String)> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data").join(file_name);
+    let content = fs::read_to_string(&path).unwrap();
+
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with('['))
+        .map(|line| {
+            let (key, value) = line.split_once('=').unwrap();
+            (key.trim().to_string(), value.trim().to_string())
+        })
+        .collect()
+}
+
+// ShortMsg and LongMsg files: Len (in bits), Msg, MD
+fn check_msg_file<A: Algorithm>(file_name: &str) {
+    let entries = parse_rsp(file_name);
+    let mut num_vectors = 0;
+
+    for vector in entries.chunks(3) {
+        assert_eq!(vector[0].0, "Len");
+        assert_eq!(vector[1].0, "Msg");
+        assert_eq!(vector[2].0, "MD");
+
+        // An empty message is written as "00"
+        let len: usize = vector[0].1.parse().unwrap();
+        let msg = &hex_to_bytes(&vector[1].1)[..len.div_ceil(8)];
+        let expected = hex_to_bytes(&vector[2].1);
+
+        assert_eq!(A::digest_bits(msg, len as u64).as_ref(), &expected[..], "{file_name}, Len = {len}");
+
+        // The streaming path must agree as well
+        let mut ctx = A::default();
+        for chunk in msg[..len / 8].chunks(A::BLOCK_BYTES / 2 + 1) {
+            ctx.update(chunk);
+        }
+        let hash = if len.is_multiple_of(8) { ctx.finalize() } else { ctx.finalize_bits(msg[len / 8], (len % 8) as u32) };
+        assert_eq!(hash.as_ref(), &expected[..], "{file_name}, Len = {len} (streaming)");
+
+        num_vectors += 1;
+    }
+
+    assert!(num_vectors > 0, "no test vector in {file_name}");
+}
+
+// Monte Carlo test as described in the SHAVS document:
+// each of the 100 checkpoints chains 1000 hashes of the three previous digests
+fn check_monte_file<A: Algorithm>(file_name: &str) {
+    let entries = parse_rsp(file_name);
+    assert_eq!(entries[0].0, "Seed");
+    let mut seed = hex_to_bytes(&entries[0].1);
+
+    let checkpoints = &entries[1..];
+    assert_eq!(checkpoints.len(), 200);
+
+    for checkpoint in checkpoints.chunks(2) {
+        assert_eq!(checkpoint[0].0, "COUNT");
+        assert_eq!(checkpoint[1].0, "MD");
+
+        let mut md: [Vec<u8>; 3] = [seed.clone(), seed.clone(), seed.clone()];
+        for _ in 3..1003 {
+            let mut ctx = A::default();
+            ctx.update(&md[0]);
+            ctx.update(&md[1]);
+            ctx.update(&md[2]);
+            let next = ctx.finalize().as_ref().to_vec();
+            md = [md[1].clone(), md[2].clone(), next];
+        }
+
+        seed = md[2].clone();
+        assert_eq!(seed, hex_to_bytes(&checkpoint[1].1), "{file_name}, COUNT = {}", checkpoint[0].1);
+    }
+}
+
+#[test]
+fn sha224_short_msg() {
+    check_msg_file::<two_two_four::Context>("shabytetestvectors/SHA224ShortMsg.rsp");
+}
+
+#[test]
+fn sha224_long_msg() {
+    check_msg_file::<two_two_four::Context>("shabytetestvectors/SHA224LongMsg.rsp");
+}
+
+#[test]
+fn sha224_monte() {
+    check_monte_file::<two_two_four::Context>("shabytetestvectors/SHA224Monte.rsp");
+}
+
+#[test]
+#[ignore = "needs SHA224ShortMsg.rsp from NIST's shabittestvectors.zip in tests/data/shabittestvectors"]
+fn sha224_short_msg_bits() {
+    check_msg_file::<two_two_four::Context>("shabittestvectors/SHA224ShortMsg.rsp");
+}
+
+#[test]
+#[ignore = "needs SHA224LongMsg.rsp from NIST's shabittestvectors.zip in tests/data/shabittestvectors"]
+fn sha224_long_msg_bits() {
+    check_msg_file::<two_two_four::Context>("shabittestvectors/SHA224LongMsg.rsp");
+}
+
+#[test]
+fn sha256_short_msg() {
+    check_msg_file::<two_five_six::Context>("shabytetestvectors/SHA256ShortMsg.rsp");
+}
+
+#[test]
+fn sha256_long_msg() {
+    check_msg_file::<two_five_six::Context>("shabytetestvectors/SHA256LongMsg.rsp");
+}
+
+#[test]
+fn sha256_monte() {
+    check_monte_file::<two_five_six::Context>("shabytetestvectors/SHA256Monte.rsp");
+}
+
+#[test]
+#[ignore = "needs SHA256ShortMsg.rsp from NIST's shabittestvectors.zip in tests/data/shabittestvectors"]
+fn sha256_short_msg_bits() {
+    check_msg_file::<two_five_six::Context>("shabittestvectors/SHA256ShortMsg.rsp");
+}
+
+#[test]
+#[ignore = "needs SHA256LongMsg.rsp from NIST's shabittestvectors.zip in tests/data/shabittestvectors"]
+fn sha256_long_msg_bits() {
+    check_msg_file::<two_five_six::Context>("shabittestvectors/SHA256LongMsg.rsp");
+}
+
+#[test]
+fn sha384_short_msg() {
+    check_msg_file::<three_eight_four::Context>("shabytetestvectors/SHA384ShortMsg.rsp");
+}
+
+#[test]
+fn sha384_long_msg() {
+    check_msg_file::<three_eight_four::Context>("shabytetestvectors/SHA384LongMsg.rsp");
+}
+
+#[test]
+fn sha384_monte() {
+    check_monte_file::<three_eight_four::Context>("shabytetestvectors/SHA384Monte.rsp");
+}
+
+#[test]
+#[ignore = "needs SHA384ShortMsg.rsp from NIST's shabittestvectors.zip in tests/data/shabittestvectors"]
+fn sha384_short_msg_bits() {
+    check_msg_file::<three_eight_four::Context>("shabittestvectors/SHA384ShortMsg.rsp");
+}
+
+#[test]
+#[ignore = "needs SHA384LongMsg.rsp from NIST's shabittestvectors.zip in tests/data/shabittestvectors"]
+fn sha384_long_msg_bits() {
+    check_msg_file::<three_eight_four::Context>("shabittestvectors/SHA384LongMsg.rsp");
+}
+
+#[test]
+fn sha512_short_msg() {
+    check_msg_file::<five_twelve::Context>("shabytetestvectors/SHA512ShortMsg.rsp");
+}
+
+#[test]
+fn sha512_long_msg() {
+    check_msg_file::<five_twelve::Context>("shabytetestvectors/SHA512LongMsg.rsp");
+}
+
+#[test]
+fn sha512_monte() {
+    check_monte_file::<five_twelve::Context>("shabytetestvectors/SHA512Monte.rsp");
+}
+
+#[test]
+#[ignore = "needs SHA512ShortMsg.rsp from NIST's shabittestvectors.zip in tests/data/shabittestvectors"]
+fn sha512_short_msg_bits() {
+    check_msg_file::<five_twelve::Context>("shabittestvectors/SHA512ShortMsg.rsp");
+}
+
+#[test]
+#[ignore = "needs SHA512LongMsg.rsp from NIST's shabittestvectors.zip in tests/data/shabittestvectors"]
+fn sha512_long_msg_bits() {
+    check_msg_file::<five_twelve::Context>("shabittestvectors/SHA512LongMsg.rsp");
+}
+
+#[test]
+#[ignore = "needs SHA512_224ShortMsg.rsp from NIST's shabytetestvectors.zip in tests/data/shabytetestvectors"]
+fn sha512_224_short_msg() {
+    check_msg_file::<five_twelve_t::Context224>("shabytetestvectors/SHA512_224ShortMsg.rsp");
+}
+
+#[test]
+#[ignore = "needs SHA512_224LongMsg.rsp from NIST's shabytetestvectors.zip in tests/data/shabytetestvectors"]
+fn sha512_224_long_msg() {
+    check_msg_file::<five_twelve_t::Context224>("shabytetestvectors/SHA512_224LongMsg.rsp");
+}
+
+#[test]
+#[ignore = "needs SHA512_224Monte.rsp from NIST's shabytetestvectors.zip in tests/data/shabytetestvectors"]
+fn sha512_224_monte() {
+    check_monte_file::<five_twelve_t::Context224>("shabytetestvectors/SHA512_224Monte.rsp");
+}
+
+#[test]
+#[ignore = "needs SHA512_256ShortMsg.rsp from NIST's shabytetestvectors.zip in tests/data/shabytetestvectors"]
+fn sha512_256_short_msg() {
+    check_msg_file::<five_twelve_t::Context256>("shabytetestvectors/SHA512_256ShortMsg.rsp");
+}
+
+#[test]
+#[ignore = "needs SHA512_256LongMsg.rsp from NIST's shabytetestvectors.zip in tests/data/shabytetestvectors"]
+fn sha512_256_long_msg() {
+    check_msg_file::<five_twelve_t::Context256>("shabytetestvectors/SHA512_256LongMsg.rsp");
+}
+
+#[test]
+#[ignore = "needs SHA512_256Monte.rsp from NIST's shabytetestvectors.zip in tests/data/shabytetestvectors"]
+fn sha512_256_monte() {
+    check_monte_file::<five_twelve_t::Context256>("shabytetestvectors/SHA512_256Monte.rsp");
+}
@@ -0,0 +1,368 @@
+// Runs the sha2sum binary like a user would and compares with the library and coreutils output formats
+
+use std::ffi::OsStr;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+use sha::{two_two_four, two_five_six, three_eight_four, five_twelve};
+use sha::output::to_hex;
+use sha::tree::{hash_tree, Symlinks};
+
+// Fresh directory per test, removed by the test when done
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("sha2sum_{name}_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn sha2sum(dir: &Path, args: &[&str], stdin: &[u8]) -> Output {
+    sha2sum_os(dir, &args.iter().map(OsStr::new).collect::<Vec<_>>(), stdin)
+}
+
+// Arguments that may not be valid UTF-8
+fn sha2sum_os(dir: &Path, args: &[&OsStr], stdin: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_sha2sum"))
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn test_gnu_output() {
+    let dir = temp_dir("gnu");
+    fs::write(dir.join("a.txt"), b"abc").unwrap();
+    fs::write(dir.join("b.bin"), vec![7u8; 100_000]).unwrap();
+
+    let output = sha2sum(&dir, &["a.txt", "b.bin"], b"");
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), format!(
+        "{}  a.txt\n{}  b.bin\n",
+        to_hex(&two_five_six::hash(b"abc")),
+        to_hex(&two_five_six::hash(&[7u8; 100_000]))
+    ));
+
+    for (bits, expected) in [
+        ("224", to_hex(&two_two_four::hash(b"abc"))),
+        ("384", to_hex(&three_eight_four::hash(b"abc"))),
+        ("512", to_hex(&five_twelve::hash(b"abc"))),
+    ] {
+        let output = sha2sum(&dir, &["-a", bits, "a.txt"], b"");
+        assert_eq!(stdout(&output), format!("{expected}  a.txt\n"));
+    }
+
+    let output = sha2sum(&dir, &["--binary", "a.txt"], b"");
+    assert_eq!(stdout(&output), format!("{} *a.txt\n", to_hex(&two_five_six::hash(b"abc"))));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_bsd_output() {
+    let dir = temp_dir("bsd");
+    fs::write(dir.join("a.txt"), b"abc").unwrap();
+
+    let output = sha2sum(&dir, &["--tag", "-a", "512", "a.txt"], b"");
+    assert_eq!(stdout(&output), format!("SHA512 (a.txt) = {}\n", to_hex(&five_twelve::hash(b"abc"))));
+
+    // Names with a backslash or a newline are escaped and the line starts with a backslash
+    fs::write(dir.join("new\nline"), b"abc").unwrap();
+    let output = sha2sum(&dir, &["--tag", "new\nline"], b"");
+    assert_eq!(stdout(&output), format!("\\SHA256 (new\\nline) = {}\n", to_hex(&two_five_six::hash(b"abc"))));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_stdin() {
+    let dir = temp_dir("stdin");
+    let expected = format!("{}  -\n", to_hex(&two_five_six::hash(b"Hello, World!")));
+
+    assert_eq!(stdout(&sha2sum(&dir, &[], b"Hello, World!")), expected);
+    assert_eq!(stdout(&sha2sum(&dir, &["-"], b"Hello, World!")), expected);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_errors() {
+    let dir = temp_dir("errors");
+    fs::write(dir.join("a.txt"), b"abc").unwrap();
+
+    // The other files are still hashed
+    let output = sha2sum(&dir, &["missing", "a.txt"], b"");
+    assert!(!output.status.success());
+    assert_eq!(stdout(&output), format!("{}  a.txt\n", to_hex(&two_five_six::hash(b"abc"))));
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "sha2sum: missing: No such file or directory\n");
+
+    assert!(!sha2sum(&dir, &["-a", "1024", "a.txt"], b"").status.success());
+    assert!(!sha2sum(&dir, &["--frobnicate"], b"").status.success());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_help_and_double_dash() {
+    let dir = temp_dir("double_dash");
+    fs::write(dir.join("-h"), b"abc").unwrap();
+
+    for args in [&["--help"][..], &["a.txt", "-h"], &["--check", "--help"]] {
+        let output = sha2sum(&dir, args, b"");
+        assert!(output.status.success());
+        assert!(stdout(&output).starts_with("Usage: sha2sum"), "{args:?}");
+    }
+
+    // After -- every argument is a file, like with coreutils
+    let output = sha2sum(&dir, &["--", "-h"], b"");
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), format!("{}  -h\n", to_hex(&two_five_six::hash(b"abc"))));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8(output.stderr.clone()).unwrap()
+}
+
+// A SHA256SUMS file for a.txt and b.txt, and a SHA512 BSD line for a.txt
+fn write_sums(dir: &Path) -> String {
+    fs::write(dir.join("a.txt"), b"abc").unwrap();
+    fs::write(dir.join("b.txt"), b"xyz").unwrap();
+
+    let sums = format!(
+        "{}  a.txt\n{} *b.txt\nSHA512 (a.txt) = {}\n",
+        to_hex(&two_five_six::hash(b"abc")),
+        to_hex(&two_five_six::hash(b"xyz")),
+        to_hex(&five_twelve::hash(b"abc"))
+    );
+    fs::write(dir.join("SUMS"), &sums).unwrap();
+    sums
+}
+
+#[test]
+fn test_check() {
+    let dir = temp_dir("check");
+    let sums = write_sums(&dir);
+
+    let output = sha2sum(&dir, &["-c", "SUMS"], b"");
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "a.txt: OK\nb.txt: OK\na.txt: OK\n");
+    assert_eq!(stderr(&output), "");
+
+    // Checksum file from stdin, and the output of the binary can be checked
+    assert!(sha2sum(&dir, &["--check"], sums.as_bytes()).status.success());
+    let generated = sha2sum(&dir, &["--tag", "-a", "384", "a.txt", "b.txt"], b"");
+    assert!(sha2sum(&dir, &["-c", "--quiet", "-"], &generated.stdout).status.success());
+
+    fs::write(dir.join("b.txt"), b"changed").unwrap();
+    let output = sha2sum(&dir, &["-c", "SUMS"], b"");
+    assert!(!output.status.success());
+    assert_eq!(stdout(&output), "a.txt: OK\nb.txt: FAILED\na.txt: OK\n");
+    assert_eq!(stderr(&output), "sha2sum: WARNING: 1 computed checksum did NOT match\n");
+
+    let output = sha2sum(&dir, &["-c", "--quiet", "SUMS"], b"");
+    assert!(!output.status.success());
+    assert_eq!(stdout(&output), "b.txt: FAILED\n");
+
+    let output = sha2sum(&dir, &["-c", "--status", "SUMS"], b"");
+    assert!(!output.status.success());
+    assert_eq!(stdout(&output), "");
+    assert_eq!(stderr(&output), "");
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_check_missing_and_malformed() {
+    let dir = temp_dir("check_missing");
+    let sums = write_sums(&dir);
+    fs::remove_file(dir.join("b.txt")).unwrap();
+
+    let output = sha2sum(&dir, &["-c", "SUMS"], b"");
+    assert!(!output.status.success());
+    assert_eq!(stdout(&output), "a.txt: OK\nb.txt: FAILED open or read\na.txt: OK\n");
+    assert_eq!(stderr(&output), "sha2sum: b.txt: No such file or directory\nsha2sum: WARNING: 1 listed file could not be read\n");
+
+    let output = sha2sum(&dir, &["-c", "--ignore-missing", "SUMS"], b"");
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "a.txt: OK\na.txt: OK\n");
+
+    // Nothing verified at all
+    fs::remove_file(dir.join("a.txt")).unwrap();
+    let output = sha2sum(&dir, &["-c", "--ignore-missing", "SUMS"], b"");
+    assert!(!output.status.success());
+    assert_eq!(stderr(&output), "sha2sum: SUMS: no file was verified\n");
+
+    // Malformed lines are only an error with --strict
+    fs::write(dir.join("a.txt"), b"abc").unwrap();
+    fs::write(dir.join("b.txt"), b"xyz").unwrap();
+    fs::write(dir.join("SUMS"), format!("{sums}garbage\nSHA1 (a.txt) = a9993e364706816aba3e25717850c26c9cd0d89d\n")).unwrap();
+    let output = sha2sum(&dir, &["-c", "SUMS"], b"");
+    assert!(output.status.success());
+    assert_eq!(stderr(&output), "sha2sum: WARNING: 2 lines are improperly formatted\n");
+    assert!(!sha2sum(&dir, &["-c", "--strict", "SUMS"], b"").status.success());
+
+    let output = sha2sum(&dir, &["-c"], b"nothing to see\n");
+    assert!(!output.status.success());
+    assert_eq!(stderr(&output), "sha2sum: -: no properly formatted checksum lines found\n");
+
+    // Check only options
+    assert!(!sha2sum(&dir, &["--strict", "a.txt"], b"").status.success());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_tree() {
+    let dir = temp_dir("tree");
+    fs::create_dir_all(dir.join("out/bin")).unwrap();
+    fs::write(dir.join("out/a.txt"), b"abc").unwrap();
+    fs::write(dir.join("out/bin/tool"), vec![1u8; 10_000]).unwrap();
+
+    let tree = hash_tree::<five_twelve::Context>(&dir.join("out"), Symlinks::Record).unwrap();
+    let summary = format!("SHA512-TREE (out) = {}\n", to_hex(&tree.digest()));
+
+    let output = sha2sum(&dir, &["--tree", "-a", "512", "out"], b"");
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), format!("{}{summary}", tree.manifest()));
+    assert_eq!(stdout(&sha2sum(&dir, &["--digest-only", "-a", "512", "--tree", "out"], b"")), summary);
+
+    // The manifest checks from inside the tree, the summary line isn't a file checksum
+    let manifest = stdout(&sha2sum(&dir, &["--tree", "out"], b""));
+    let output = sha2sum(&dir.join("out"), &["-c", "-"], manifest.as_bytes());
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "a.txt: OK\nbin/tool: OK\n");
+
+    assert!(!sha2sum(&dir, &["--tree", "missing"], b"").status.success());
+    assert!(!sha2sum(&dir, &["--tree"], b"").status.success());
+    assert!(!sha2sum(&dir, &["--tree", "--check", "out"], b"").status.success());
+    assert!(!sha2sum(&dir, &["--digest-only", "out/a.txt"], b"").status.success());
+
+    // Not a subcommand, a file named tree is hashed like any other
+    fs::write(dir.join("tree"), b"abc").unwrap();
+    let output = sha2sum(&dir, &["tree"], b"");
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), format!("{}  tree\n", to_hex(&two_five_six::hash(b"abc"))));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_tree_check() {
+    use std::os::unix::fs::symlink;
+
+    let dir = temp_dir("tree_check");
+    fs::create_dir_all(dir.join("out/bin")).unwrap();
+    fs::write(dir.join("out/a.txt"), b"abc").unwrap();
+    symlink("a.txt", dir.join("out/link")).unwrap();
+    symlink("b) -> c", dir.join("out/x) -> y")).unwrap();
+
+    // Every line of the output is either checked or skipped, --strict passes
+    let manifest = stdout(&sha2sum(&dir, &["--tree", "out"], b""));
+    let output = sha2sum(&dir.join("out"), &["-c", "--strict", "-"], manifest.as_bytes());
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "a.txt: OK\nlink: OK\nx) -> y: OK\n");
+    assert_eq!(stderr(&output), "");
+
+    fs::remove_file(dir.join("out/link")).unwrap();
+    symlink("bin", dir.join("out/link")).unwrap();
+    let output = sha2sum(&dir.join("out"), &["-c", "-"], manifest.as_bytes());
+    assert!(!output.status.success());
+    assert_eq!(stdout(&output), "a.txt: OK\nlink: FAILED\nx) -> y: OK\n");
+
+    fs::remove_file(dir.join("out/link")).unwrap();
+    let output = sha2sum(&dir.join("out"), &["-c", "-"], manifest.as_bytes());
+    assert!(!output.status.success());
+    assert_eq!(stdout(&output), "a.txt: OK\nlink: FAILED open or read\nx) -> y: OK\n");
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_non_utf8_name() {
+    use std::os::unix::ffi::OsStrExt;
+
+    let dir = temp_dir("non_utf8");
+    let name = OsStr::from_bytes(b"f\xff");
+    fs::write(dir.join(name), b"abc").unwrap();
+    let hex = to_hex(&two_five_six::hash(b"abc"));
+
+    // The name is written back as it was given
+    let output = sha2sum_os(&dir, &[name], b"");
+    assert!(output.status.success());
+    assert_eq!(output.stdout, [format!("{hex}  ").as_bytes(), b"f\xff\n"].concat());
+
+    let output = sha2sum_os(&dir, &[OsStr::new("--tag"), OsStr::new("--"), name], b"");
+    assert_eq!(output.stdout, [&b"SHA256 (f\xff) = "[..], format!("{hex}\n").as_bytes()].concat());
+
+    // Still an option, even if it can't be printed as is
+    let output = sha2sum_os(&dir, &[OsStr::from_bytes(b"--\xff")], b"");
+    assert!(!output.status.success());
+    assert!(stderr(&output).starts_with("sha2sum: unrecognized option '--\u{fffd}'\n"));
+
+    // What was printed checks back, from a checksum file that isn't UTF-8
+    let listing = sha2sum_os(&dir, &[name], b"").stdout;
+    let output = sha2sum(&dir, &["-c", "-"], &listing);
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"f\xff: OK\n");
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_check_non_utf8_line() {
+    let dir = temp_dir("check_non_utf8");
+    fs::write(dir.join("a.txt"), b"abc").unwrap();
+    let hex = to_hex(&two_five_six::hash(b"abc"));
+
+    // Only the line that can't be parsed is reported, the others are still checked
+    let listing = [format!("{hex}  a.txt\n").as_bytes(), b"\xe9\xe9  b.txt\n", format!("{hex}  a.txt\n").as_bytes()].concat();
+    let output = sha2sum(&dir, &["-c", "-"], &listing);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "a.txt: OK\na.txt: OK\n");
+    assert_eq!(stderr(&output), "sha2sum: WARNING: 1 line is improperly formatted\n");
+    assert!(!sha2sum(&dir, &["-c", "--strict", "-"], &listing).status.success());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_jobs() {
+    let dir = temp_dir("jobs");
+    let mut names = Vec::new();
+    for i in 0..40u32 {
+        let name = format!("{i}.bin");
+        fs::write(dir.join(&name), vec![i as u8; i as usize * 500]).unwrap();
+        names.push(name);
+    }
+    names.insert(5, String::from("missing"));
+    let args: Vec<&str> = names.iter().map(String::as_str).collect();
+
+    // Same lines in the same order whatever the number of threads
+    let sequential = sha2sum(&dir, &args, b"");
+    for jobs in ["0", "4", "64"] {
+        let parallel = sha2sum(&dir, &[&["-j", jobs], &args[..]].concat(), b"");
+        assert!(!parallel.status.success());
+        assert_eq!(stdout(&parallel), stdout(&sequential));
+        assert_eq!(stderr(&parallel), "sha2sum: missing: No such file or directory\n");
+    }
+    assert_eq!(stdout(&sequential).lines().count(), 40);
+
+    assert!(!sha2sum(&dir, &["-j", "many", "0.bin"], b"").status.success());
+
+    fs::remove_dir_all(dir).unwrap();
+}